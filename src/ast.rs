@@ -9,7 +9,9 @@ pub mod node;
 pub mod ops;
 pub mod symbol;
 pub mod token;
+pub mod r#type;
 
 pub use expr::*;
 pub use item::*;
 pub use node::*;
+pub use r#type::*;
@@ -1,7 +1,7 @@
 use super::node::NodeId;
-use super::ops::BinaryOp;
-use super::token::TokenData;
-use super::Ptr;
+use super::ops::{BinaryOp, UnaryOp};
+use super::token::{SrcSpan, TokenData};
+use super::{Ptr, Type};
 
 // pub enum LitKind{
 //     Str,
@@ -19,6 +19,17 @@ use super::Ptr;
 //     pub path: Vec<TokenData<'s>>,
 // }
 
+// value of a literal as written, its type is decided by the type checker
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lit {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    // escape sequences are already replaced
+    Str(String),
+}
+
 #[derive(Clone, Copy)]
 pub enum MutKind {
     Mutable,
@@ -28,37 +39,115 @@ pub enum MutKind {
 pub struct VarDecl<'s> {
     pub mutable: MutKind,
     pub bound: TokenData<'s>,
+    pub ty: Option<Type>,
     pub value: Expr<'s>,
 }
 
 impl<'s> VarDecl<'s> {
-    pub fn new(m: MutKind, b: TokenData<'s>, val: Expr<'s>) -> Self {
+    pub fn new(m: MutKind, b: TokenData<'s>, ty: Option<Type>, val: Expr<'s>) -> Self {
         Self {
             mutable: m,
             bound: b,
+            ty,
             value: val,
         }
     }
 }
 
-// pub enum CtrlFlow{
-//     Continue,
-//     Break,
-// }
+// `for var in start..end step s`, counts up while var < end
+pub struct ForRange<'s> {
+    pub var: TokenData<'s>,
+    // the loop variable is a binding of its own
+    pub var_nid: NodeId,
+    pub start: Expr<'s>,
+    pub end: Expr<'s>,
+    // 1 when left out
+    pub step: Option<Expr<'s>>,
+}
+
+// `name: value` in a struct literal
+pub struct FieldInit<'s> {
+    pub name: TokenData<'s>,
+    pub value: Expr<'s>,
+}
+
+pub enum PatternKind<'s> {
+    // `_` matches anything
+    Wildcard,
+    // `1`, `-2.5`, `'a'` or `true`, never a string
+    Lit(Lit),
+    // matches anything and names it in the arm
+    // unless it is the name of a variant without a payload
+    Bind(TokenData<'s>),
+    // `Rect(w, _)`, an enum variant and patterns for its payload
+    Variant {
+        name: TokenData<'s>,
+        args: Vec<Pattern<'s>>,
+    },
+    // `(lo, _)`, a pattern for every element of a tuple
+    Tuple(Vec<Pattern<'s>>),
+}
+
+pub struct Pattern<'s> {
+    pub kind: PatternKind<'s>,
+    // a binding pattern declares a symbol
+    pub nid: NodeId,
+    pub span: SrcSpan,
+}
+
+impl<'s> Pattern<'s> {
+    pub fn new(nid: NodeId, span: SrcSpan, kind: PatternKind<'s>) -> Self {
+        Self { kind, nid, span }
+    }
+}
+
+// `case pattern => body`
+pub struct MatchArm<'s> {
+    pub pat: Pattern<'s>,
+    pub body: Expr<'s>,
+}
 
 pub enum ExprKind<'s> {
     // Place(Place<'s>),
     Var(TokenData<'s>),
-    Lit(f64),
+    Lit(Lit),
     Binary {
         op: BinaryOp,
         lhs: Expr<'s>,
         rhs: Expr<'s>,
     },
+    Unary {
+        op: UnaryOp,
+        operand: Expr<'s>,
+    },
     Call {
         callee: Expr<'s>,
         args: Vec<Expr<'s>>,
     },
+    // `Point { x: 1.0, y: 2.0 }`, every field is given
+    StructLit {
+        name: TokenData<'s>,
+        fields: Vec<FieldInit<'s>>,
+    },
+    // `base.field`
+    Field {
+        base: Expr<'s>,
+        field: TokenData<'s>,
+    },
+    // `(1, 2.0)`, two or more elements
+    TupleLit(Vec<Expr<'s>>),
+    // `base.0`
+    TupleField {
+        base: Expr<'s>,
+        index: usize,
+    },
+    // `[1.0, 2.0]`
+    ArrayLit(Vec<Expr<'s>>),
+    // `base[index]`, checked against the length at run time
+    Index {
+        base: Expr<'s>,
+        index: Expr<'s>,
+    },
     If {
         cond: Expr<'s>,
         if_body: Expr<'s>,
@@ -66,6 +155,13 @@ pub enum ExprKind<'s> {
     },
     Block(Vec<Expr<'s>>),
     Decl(VarDecl<'s>),
+    // `val (lo, hi) = pair`, the pattern must match any value
+    Destructure {
+        mutable: MutKind,
+        pat: Pattern<'s>,
+        ty: Option<Type>,
+        value: Expr<'s>,
+    },
     Let {
         bound: Vec<Expr<'s>>,
         let_body: Expr<'s>,
@@ -74,18 +170,55 @@ pub enum ExprKind<'s> {
         cond: Expr<'s>,
         while_body: Expr<'s>,
     },
+    // runs until a `break`, which gives the value of the loop
+    Loop(Expr<'s>),
+    For {
+        range: ForRange<'s>,
+        for_body: Expr<'s>,
+    },
+    // the first arm whose pattern matches gives the value
+    Match {
+        scrutinee: Expr<'s>,
+        arms: Vec<MatchArm<'s>>,
+    },
+    // jump to the end or the next iteration of the innermost loop
+    // only `loop` can break with a value
+    Break(Option<Expr<'s>>),
+    Continue,
+    // leaves the function with the value
+    Return(Expr<'s>),
+    // placeholder for code that failed to parse
+    Error,
+}
+
+// functions the compiler provides, a definition with the same name hides them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    // number of elements of an array
+    Len,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "len" => Some(Builtin::Len),
+            _ => None,
+        }
+    }
 }
 
 pub struct Expr<'s> {
     pub kind: Ptr<ExprKind<'s>>,
     pub nid: NodeId,
+    pub span: SrcSpan,
 }
 
 impl<'s> Expr<'s> {
-    pub fn new(nid: NodeId, ek: ExprKind<'s>) -> Self {
+    pub fn new(nid: NodeId, span: SrcSpan, ek: ExprKind<'s>) -> Self {
         Expr {
             kind: Ptr::new(ek),
             nid,
+            span,
         }
     }
 }
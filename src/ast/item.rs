@@ -1,10 +1,13 @@
+use std::collections::HashMap;
+
 use super::expr::Expr;
 use super::node::NodeId;
 use super::token::TokenData;
+use super::{Type, TypeKind};
 
 pub struct FuncArg<'s> {
     pub name: TokenData<'s>,
-    // ty: Option<Type>,
+    pub ty: Option<Type>,
     pub nid: NodeId,
 }
 
@@ -15,14 +18,18 @@ impl<'s> FuncArg<'s> {
 }
 
 impl<'s> FuncArg<'s> {
-    pub fn new(nid: NodeId, t: TokenData<'s>) -> Self {
-        FuncArg { name: t, nid }
+    pub fn new(nid: NodeId, t: TokenData<'s>, ty: Option<Type>) -> Self {
+        FuncArg { name: t, ty, nid }
     }
 }
 
 pub struct FuncProto<'s> {
     pub name: TokenData<'s>,
     pub args: Vec<FuncArg<'s>>,
+    // annotated return type
+    pub ret: Option<Type>,
+    // `...` after the arguments, extern only
+    pub variadic: bool,
 }
 
 pub struct Function<'s> {
@@ -30,6 +37,32 @@ pub struct Function<'s> {
     pub body: Expr<'s>,
 }
 
+// `name: type` in a struct
+pub struct FieldDef<'s> {
+    pub name: TokenData<'s>,
+    pub ty: Type,
+}
+
+// fields are laid out in declaration order
+pub struct StructDef<'s> {
+    pub name: TokenData<'s>,
+    pub fields: Vec<FieldDef<'s>>,
+}
+
+// `Circle(f64)` or `Empty` in an enum
+pub struct VariantDef<'s> {
+    pub name: TokenData<'s>,
+    // the constructor is a symbol like a function
+    pub nid: NodeId,
+    pub payload: Vec<Type>,
+}
+
+// tags are given in declaration order
+pub struct EnumDef<'s> {
+    pub name: TokenData<'s>,
+    pub variants: Vec<VariantDef<'s>>,
+}
+
 // pub enum ImportStmt<'s>{
 //     SimpleImport(Place<'s>),
 //     FromImport(Place<'s>, Vec<TokenData<'s>>),
@@ -38,7 +71,11 @@ pub struct Function<'s> {
 pub enum ItemKind<'s> {
     Func(Function<'s>),
     Extern(FuncProto<'s>),
+    Struct(StructDef<'s>),
+    Enum(EnumDef<'s>),
     // Import(ImportStmt<'s>),
+    // placeholder for an item that failed to parse
+    Error,
 }
 
 pub struct Item<'s> {
@@ -61,3 +98,65 @@ pub struct Module<'s> {
     pub decl: TokenData<'s>, //ModDecl<'s>,
     pub body: Vec<Item<'s>>,
 }
+
+#[derive(Debug, Clone)]
+pub struct VariantInfo {
+    pub enum_name: String,
+    pub name: String,
+    pub tag: u32,
+    pub payload: Vec<Type>,
+}
+
+// the variants of every enum in a module
+// variant names share the module namespace with functions, so they are unique
+#[derive(Default)]
+pub struct EnumTable {
+    enums: HashMap<String, Vec<VariantInfo>>,
+    // enum name and tag of each variant
+    variants: HashMap<String, (String, usize)>,
+}
+
+impl EnumTable {
+    pub fn new(m: &Module) -> Self {
+        let mut table = Self::default();
+        for item in &m.body {
+            if let ItemKind::Enum(def) = &item.kind {
+                let enum_name = String::from(def.name.span);
+                let variants: Vec<_> = def
+                    .variants
+                    .iter()
+                    .enumerate()
+                    .map(|(tag, v)| VariantInfo {
+                        enum_name: enum_name.clone(),
+                        name: String::from(v.name.span),
+                        tag: tag as u32,
+                        payload: v.payload.clone(),
+                    })
+                    .collect();
+
+                for (tag, v) in variants.iter().enumerate() {
+                    let entry = (enum_name.clone(), tag);
+                    table.variants.entry(v.name.clone()).or_insert(entry);
+                }
+                table.enums.entry(enum_name).or_insert(variants);
+            }
+        }
+        table
+    }
+
+    pub fn variants(&self, enum_name: &str) -> Option<&[VariantInfo]> {
+        self.enums.get(enum_name).map(Vec::as_slice)
+    }
+
+    pub fn variant(&self, name: &str) -> Option<&VariantInfo> {
+        let (enum_name, tag) = self.variants.get(name)?;
+        self.enums.get(enum_name)?.get(*tag)
+    }
+
+    pub fn is_enum(&self, ty: &Type) -> bool {
+        match ty.kind() {
+            TypeKind::Named(name) => self.enums.contains_key(name),
+            _ => false,
+        }
+    }
+}
@@ -9,6 +9,14 @@ pub enum BinaryOp {
     Sub,
     Mul,
     Div,
+    Rem,
+
+    // bitwise, integers only
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
 
     // logical
     LogicalAnd,
@@ -16,15 +24,58 @@ pub enum BinaryOp {
 
     // comparison
     Et,
+    NotEt,
     Lt,
     LtEt,
+    Gt,
+    GtEt,
+}
+
+impl BinaryOp {
+    // operator as written in source
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Assign => "=",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::LogicalAnd => "&&",
+            BinaryOp::LogicalOr => "||",
+            BinaryOp::Et => "==",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEt => "<=",
+            BinaryOp::NotEt => "!=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEt => ">=",
+        }
+    }
 }
 
-// #[derive(Debug)]
-// pub enum UnaryOp{
-//     LogicalNotOp,
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    // arithmatic
+    Neg,
 
-//     // memory ops
-//     AddrOfOp,
-//     DerefOp,
-// }
+    // logical
+    LogicalNot,
+    // memory ops
+    // AddrOfOp,
+    // DerefOp,
+}
+
+impl UnaryOp {
+    // operator as written in source
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::LogicalNot => "!",
+        }
+    }
+}
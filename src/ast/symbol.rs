@@ -4,6 +4,7 @@ use std::collections::HashMap;
 // use std::num::NonZeroUsize;
 
 use crate::ast::node::NodeId;
+use crate::ast::token::{SrcSpan, TokenData};
 
 type SymIdRepr = u32;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -31,11 +32,14 @@ pub struct ScopedSymEntry {
     nid: NodeId,
     pub sid: SymId,
     sscope: SymScope,
+    // where the symbol was declared
+    pub span: SrcSpan,
 }
 
 #[derive(Debug, Clone)]
 pub enum ScopedInsertErr {
-    RedefinedLocal(ScopedSymEntry), // TODO: support shadowing
+    // same name twice in one scope, inner scopes may shadow instead
+    RedefinedLocal(ScopedSymEntry),
     CompilerBug(String),
 }
 
@@ -54,27 +58,27 @@ impl<'s> Default for ScopedSymTable<'s> {
 }
 
 impl<'s> ScopedSymTable<'s> {
-    fn lookup(&self, s: &'s str) -> Option<&SymId> {
-        let entry = self.symbols.get(s)?;
-        Some(&entry.sid)
+    fn lookup(&self, s: &'s str) -> Option<&ScopedSymEntry> {
+        self.symbols.get(s)
     }
 
     fn insert(
         &mut self,
         nid: NodeId,
-        s: &'s str,
+        name: &TokenData<'s>,
         sid: SymId,
         kind: SymScope,
     ) -> ScopedInsertResult {
-        match self.symbols.get(s) {
+        match self.symbols.get(name.span) {
             Some(entry) => Err(ScopedInsertErr::RedefinedLocal(*entry)),
             None => {
                 let entry = ScopedSymEntry {
                     nid,
                     sid,
                     sscope: kind,
+                    span: name.src_span(),
                 };
-                self.symbols.insert(s, entry);
+                self.symbols.insert(name.span, entry);
                 Ok(sid)
             }
         }
@@ -107,20 +111,21 @@ impl<'s> ScopedSymbolStack<'s> {
         self.scope_stack.push(ScopedSymTable::default());
     }
 
-    pub fn pop_scope(&mut self) {
+    pub fn pop_scope(&mut self) -> Option<ScopedInsertErr> {
         // unpack symbols into global table
-        if let Some(scope) = self.scope_stack.pop() {
-            for (_, entry) in scope.symbols.into_iter() {
-                if let Some(old_sid) = self.mod_table.insert(entry.nid, entry.sid) {
-                    unimplemented!(
-                        "remap NodeID {:?} from {:?} to second {:?}",
-                        entry.nid,
-                        old_sid,
-                        entry.sid
-                    )
-                }
+        let scope = self.scope_stack.pop()?;
+        for (_, entry) in scope.symbols.into_iter() {
+            if let Some(old_sid) = self.mod_table.insert(entry.nid, entry.sid) {
+                // every declaration has its own node,
+                // even when it shadows an outer name
+                let msg = format!(
+                    "NodeId {:?} mapped to {:?} and {:?}",
+                    entry.nid, old_sid, entry.sid
+                );
+                return Some(ScopedInsertErr::CompilerBug(msg));
             }
         }
+        None
     }
 
     pub fn finish_resolve(self) -> ModSymTable {
@@ -128,35 +133,44 @@ impl<'s> ScopedSymbolStack<'s> {
     }
 
     pub fn lookup(&self, sym: &'s str) -> Option<&SymId> {
-        for scope in self.scope_stack.iter().rev() {
-            match scope.lookup(sym) {
-                Some(sid) => {
-                    // eprintln!("Resolved {:?} to {:?}", sym, sid);
-                    return Some(sid);
-                }
-                _ => continue,
-            }
-        }
-        None
+        let entry = self.lookup_entry(sym)?;
+        // eprintln!("Resolved {:?} to {:?}", sym, entry.sid);
+        Some(&entry.sid)
+    }
+
+    // innermost declaration of sym
+    pub fn lookup_entry(&self, sym: &'s str) -> Option<&ScopedSymEntry> {
+        self.scope_stack
+            .iter()
+            .rev()
+            .find_map(|scope| scope.lookup(sym))
+    }
+
+    // every name reachable from the innermost scope
+    pub fn visible_names(&self) -> impl Iterator<Item = &'s str> + '_ {
+        self.scope_stack
+            .iter()
+            .flat_map(|scope| scope.symbols.keys().copied())
     }
 
-    pub fn insert_local(&mut self, nid: NodeId, sym: &'s str) -> ScopedInsertResult {
+    pub fn insert_local(&mut self, nid: NodeId, sym: &TokenData<'s>) -> ScopedInsertResult {
         self.insert(nid, sym, SymScope::Local)
     }
 
     // create nid => sid mapping
     pub fn insert_local_reuse(&mut self, nid: NodeId, sid: SymId) -> Option<ScopedInsertErr> {
-        let _old_entry = self.mod_table.insert(nid, sid)?;
+        let old_sid = self.mod_table.insert(nid, sid)?;
 
         // Error, a variable use is being mapped to multiple
-        unimplemented!("AST node being mapped to multiple SID")
+        let msg = format!("NodeId {:?} used as {:?} and {:?}", nid, old_sid, sid);
+        Some(ScopedInsertErr::CompilerBug(msg))
     }
 
-    pub fn insert_func(&mut self, nid: NodeId, sym: &'s str) -> ScopedInsertResult {
+    pub fn insert_func(&mut self, nid: NodeId, sym: &TokenData<'s>) -> ScopedInsertResult {
         self.insert(nid, sym, SymScope::Module)
     }
 
-    fn insert(&mut self, nid: NodeId, sym: &'s str, kind: SymScope) -> ScopedInsertResult {
+    fn insert(&mut self, nid: NodeId, sym: &TokenData<'s>, kind: SymScope) -> ScopedInsertResult {
         let sid = self.sid.shift();
 
         // eprintln!("Creating {:?} from {:?}", sid, sym);
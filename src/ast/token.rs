@@ -16,6 +16,7 @@ pub enum LitKind {
     Char,
     Float,
     Str,
+    Bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -39,8 +40,16 @@ pub enum KwKind {
     Let,
     In,
     Mut,
-    // Loop,
+    Loop,
     While,
+    For,
+    Break,
+    Continue,
+    Return,
+    Match,
+    Case,
+    Struct,
+    Enum,
     // modules
     // From,
     // Import,
@@ -51,8 +60,6 @@ pub enum KwKind {
 pub enum ReservedKind {
     This,
     KwSelf,
-    Enum,
-    Struct,
     Class,
     Rec,
     Data,
@@ -68,10 +75,6 @@ pub enum ReservedKind {
     Exposing,
     Async,
     Await,
-    For,
-    Loop,
-    Match,
-    Case,
     Switch,
     And,
     Or,
@@ -90,13 +93,10 @@ pub enum ReservedKind {
     Go,
     Try,
     Catch,
-    Break,
-    Continue,
     GoTo,
     Impl,
     Fn,
     Def,
-    Return,
     Yield,
     Throw,
     Raise,
@@ -135,8 +135,10 @@ pub enum Token {
     // delimiter
     OpenParen,
     ClosedParen,
-    // OpenSquare,
-    // ClosedSquare,
+    OpenSquare,
+    ClosedSquare,
+    OpenCurly,
+    ClosedCurly,
     Comma,
     ColonSingle,
     // ColonDouble,
@@ -144,8 +146,8 @@ pub enum Token {
     Kw(KwKind),
 
     // Errors
-    // UnclosedChar,
-    // UnclosedStr,
+    UnclosedChar,
+    UnclosedStr,
     UnknownChunk,
     Reserved(ReservedKind),
 }
@@ -165,6 +167,21 @@ impl<'s> TokenData<'s> {
             loc: l,
         }
     }
+
+    // location range covered by this token in the source
+    pub fn src_span(&self) -> SrcSpan {
+        let mut end = self.loc;
+        for c in self.span.chars() {
+            end = match c {
+                '\n' => end.next_line(),
+                _ => end.next_col(),
+            };
+        }
+        SrcSpan {
+            start: self.loc,
+            end,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -201,3 +218,24 @@ impl TokenLoc {
         }
     }
 }
+
+// start is inclusive, end is exclusive
+#[derive(Clone, Copy, Debug)]
+pub struct SrcSpan {
+    pub start: TokenLoc,
+    pub end: TokenLoc,
+}
+
+impl SrcSpan {
+    pub fn new(start: TokenLoc, end: TokenLoc) -> Self {
+        Self { start, end }
+    }
+
+    // smallest span covering both
+    pub fn to(&self, other: SrcSpan) -> Self {
+        Self {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
@@ -1,30 +1,268 @@
+use std::collections::HashMap;
+use std::fmt;
 
-use self::ast::Ptr;
+use super::node::NodeId;
+use super::Ptr;
 
-#[derive(Debug)]
-pub enum IntWidth{
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntWidth {
     Int8,
     Int16,
     Int32,
     Int64,
 }
 
-#[derive(Debug)]
-pub enum FloatWidth{
+impl IntWidth {
+    pub fn bits(self) -> u32 {
+        match self {
+            IntWidth::Int8 => 8,
+            IntWidth::Int16 => 16,
+            IntWidth::Int32 => 32,
+            IntWidth::Int64 => 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatWidth {
     Float32,
-    Float64
+    Float64,
+}
+
+// placeholder for a type that is not known yet
+// only exists while type inference runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeVar(u32);
+
+impl TypeVar {
+    pub fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
 }
 
-#[derive(Debug)]
-pub enum TypeKind<'s>{
+#[derive(Debug, PartialEq)]
+pub enum TypeKind {
     Int(IntWidth),
+    // unsigned, `/`, `%`, `>>` and comparisons differ from Int
+    UInt(IntWidth),
     Float(FloatWidth),
+    Bool,
     Char,
-    Enum(&'s str),
-    Array(Type<'s>), //Option<usize>, 
-    Struct(&'s str),
+    // value of statements like `while` and `val`, has no data
+    Nil,
+    // pointer to a null terminated, constant C string
+    Str,
+    // signature of a function, values of it are function pointers
+    // only extern functions are variadic
+    Func {
+        args: Vec<Type>,
+        ret: Type,
+        variadic: bool,
+    },
+    Var(TypeVar),
+    // a `struct` or `enum` item, by name
+    Named(String),
+    // `[f64]`, elements in memory seen through a pointer and a length
+    Array(Type),
+    // `(i64, f64)`, an anonymous struct of two or more elements
+    Tuple(Vec<Type>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type {
+    kind: Ptr<TypeKind>,
 }
 
-pub struct Type<'s>{
-    kind: Ptr<TypeKind<'s>>,
-}
\ No newline at end of file
+impl Type {
+    pub fn new(kind: TypeKind) -> Self {
+        Self {
+            kind: Ptr::new(kind),
+        }
+    }
+
+    pub fn kind(&self) -> &TypeKind {
+        &self.kind
+    }
+
+    pub fn int(w: IntWidth) -> Self {
+        Self::new(TypeKind::Int(w))
+    }
+
+    pub fn uint(w: IntWidth) -> Self {
+        Self::new(TypeKind::UInt(w))
+    }
+
+    pub fn float(w: FloatWidth) -> Self {
+        Self::new(TypeKind::Float(w))
+    }
+
+    pub fn bool() -> Self {
+        Self::new(TypeKind::Bool)
+    }
+
+    pub fn char() -> Self {
+        Self::new(TypeKind::Char)
+    }
+
+    pub fn nil() -> Self {
+        Self::new(TypeKind::Nil)
+    }
+
+    pub fn str() -> Self {
+        Self::new(TypeKind::Str)
+    }
+
+    pub fn func(args: Vec<Type>, ret: Type) -> Self {
+        Self::new(TypeKind::Func {
+            args,
+            ret,
+            variadic: false,
+        })
+    }
+
+    // C functions taking extra arguments after `args`, like printf
+    pub fn variadic_func(args: Vec<Type>, ret: Type) -> Self {
+        Self::new(TypeKind::Func {
+            args,
+            ret,
+            variadic: true,
+        })
+    }
+
+    pub fn named(name: &str) -> Self {
+        Self::new(TypeKind::Named(String::from(name)))
+    }
+
+    pub fn array(elem: Type) -> Self {
+        Self::new(TypeKind::Array(elem))
+    }
+
+    pub fn tuple(elems: Vec<Type>) -> Self {
+        Self::new(TypeKind::Tuple(elems))
+    }
+
+    pub fn var(v: TypeVar) -> Self {
+        Self::new(TypeKind::Var(v))
+    }
+
+    // type of values nothing constrains
+    pub fn default_value() -> Self {
+        Self::float(FloatWidth::Float64)
+    }
+
+    // names usable in annotations
+    pub fn from_name(name: &str) -> Option<Self> {
+        let kind = match name {
+            "i8" => TypeKind::Int(IntWidth::Int8),
+            "i16" => TypeKind::Int(IntWidth::Int16),
+            "i32" => TypeKind::Int(IntWidth::Int32),
+            "i64" => TypeKind::Int(IntWidth::Int64),
+            "u8" => TypeKind::UInt(IntWidth::Int8),
+            "u16" => TypeKind::UInt(IntWidth::Int16),
+            "u32" => TypeKind::UInt(IntWidth::Int32),
+            "u64" => TypeKind::UInt(IntWidth::Int64),
+            "f32" => TypeKind::Float(FloatWidth::Float32),
+            "f64" => TypeKind::Float(FloatWidth::Float64),
+            "bool" => TypeKind::Bool,
+            "char" => TypeKind::Char,
+            "nil" => TypeKind::Nil,
+            "str" => TypeKind::Str,
+            _ => return None,
+        };
+        Some(Self::new(kind))
+    }
+
+    // signed or unsigned
+    pub fn is_int(&self) -> bool {
+        matches!(self.kind(), TypeKind::Int(_) | TypeKind::UInt(_))
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self.kind(), TypeKind::UInt(_))
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self.kind(), TypeKind::Float(_))
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_int() || self.is_float()
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind() {
+            TypeKind::Int(w) => write!(f, "i{}", w.bits()),
+            TypeKind::UInt(w) => write!(f, "u{}", w.bits()),
+            TypeKind::Float(FloatWidth::Float32) => write!(f, "f32"),
+            TypeKind::Float(FloatWidth::Float64) => write!(f, "f64"),
+            TypeKind::Bool => write!(f, "bool"),
+            TypeKind::Char => write!(f, "char"),
+            TypeKind::Nil => write!(f, "nil"),
+            TypeKind::Str => write!(f, "str"),
+            TypeKind::Func {
+                args,
+                ret,
+                variadic,
+            } => {
+                write!(f, "fun(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                if *variadic {
+                    write!(f, "{}...", if args.is_empty() { "" } else { ", " })?;
+                }
+                write!(f, "): {}", ret)
+            }
+            TypeKind::Var(_) => write!(f, "_"),
+            TypeKind::Named(name) => write!(f, "{}", name),
+            TypeKind::Array(elem) => write!(f, "[{}]", elem),
+            TypeKind::Tuple(elems) => {
+                write!(f, "(")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", elem)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+// output of type checking
+// every expression, argument and item maps to its type
+pub struct TypeTable {
+    types: HashMap<NodeId, Type>,
+}
+
+impl TypeTable {
+    pub fn new() -> Self {
+        Self {
+            types: HashMap::new(),
+        }
+    }
+
+    pub fn lookup(&self, nid: &NodeId) -> Option<&Type> {
+        self.types.get(nid)
+    }
+
+    pub fn insert(&mut self, nid: NodeId, ty: Type) -> Option<Type> {
+        self.types.insert(nid, ty)
+    }
+}
+
+impl Default for TypeTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
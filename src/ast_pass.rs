@@ -1,8 +1,11 @@
 use crate::ast::Module;
 
 pub mod debug;
+pub mod match_check;
+pub mod mutability;
 pub mod name_resolve;
 pub mod to_llvm;
+pub mod type_check;
 
 // pub trait GlobalPass{}
 pub trait ModulePass<'s> {
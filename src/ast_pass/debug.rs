@@ -1,4 +1,4 @@
-use crate::ast::ops::BinaryOp;
+use crate::ast::ops::{BinaryOp, UnaryOp};
 use crate::ast::token::TokenData;
 use crate::ast::*;
 
@@ -60,6 +60,9 @@ impl<'s> PrinterState<'s> {
         match &item.kind {
             ItemKind::Func(f) => self.print_function(f),
             ItemKind::Extern(e) => self.print_extern(e),
+            ItemKind::Struct(def) => self.print_struct(def),
+            ItemKind::Enum(def) => self.print_enum(def),
+            ItemKind::Error => eprintln!("Error Item"),
             // ItemKind::Import(im) => self.print_imports(im),
         }
     }
@@ -104,6 +107,17 @@ impl<'s> PrinterState<'s> {
             "Function {:?} @ line {}, col {}",
             tok.span, loc.line, loc.column
         );
+        for arg in &fp.args {
+            self.print_depth();
+            match &arg.ty {
+                Some(ty) => eprintln!("arg {:?}: {}", arg.as_str(), ty),
+                None => eprintln!("arg {:?}", arg.as_str()),
+            }
+        }
+        if let Some(ret) = &fp.ret {
+            self.print_depth();
+            eprintln!("returns {}", ret);
+        }
     }
 
     fn print_extern(&mut self, e: &FuncProto<'s>) {
@@ -111,6 +125,31 @@ impl<'s> PrinterState<'s> {
         self.print_func_proto(e);
     }
 
+    fn print_struct(&mut self, def: &StructDef<'s>) {
+        eprintln!("Struct {:?}", def.name.span);
+        self.dive();
+        for field in &def.fields {
+            self.print_depth();
+            eprintln!("field {:?}: {}", field.name.span, field.ty);
+        }
+        self.rise();
+    }
+
+    fn print_enum(&mut self, def: &EnumDef<'s>) {
+        eprintln!("Enum {:?}", def.name.span);
+        self.dive();
+        for variant in &def.variants {
+            self.print_depth();
+            if variant.payload.is_empty() {
+                eprintln!("variant {:?}", variant.name.span);
+            } else {
+                let payload: Vec<_> = variant.payload.iter().map(Type::to_string).collect();
+                eprintln!("variant {:?}({})", variant.name.span, payload.join(", "));
+            }
+        }
+        self.rise();
+    }
+
     fn print_function(&mut self, f: &Function<'s>) {
         // eprint!("- ");
         self.print_func_proto(&f.proto);
@@ -128,7 +167,50 @@ impl<'s> PrinterState<'s> {
             // ExprKind::Place(p) => self.print_place(p),
             ExprKind::Block(block) => self.print_expr_block(block),
             ExprKind::Call { callee, args } => self.print_call(callee, args),
+            ExprKind::StructLit { name, fields } => self.print_struct_lit(name, fields),
+            ExprKind::Field { base, field } => {
+                eprintln!("Field {:?} of:", field.span);
+                self.dive();
+                self.print_depth();
+                self.print_expr(base);
+                self.rise();
+            }
+            ExprKind::TupleField { base, index } => {
+                eprintln!("Element {} of:", index);
+                self.dive();
+                self.print_depth();
+                self.print_expr(base);
+                self.rise();
+            }
+            ExprKind::TupleLit(elems) => {
+                eprintln!("Tuple of {}:", elems.len());
+                self.dive();
+                for elem in elems {
+                    self.print_depth();
+                    self.print_expr(elem);
+                }
+                self.rise();
+            }
+            ExprKind::ArrayLit(elems) => {
+                eprintln!("Array of {}:", elems.len());
+                self.dive();
+                for elem in elems {
+                    self.print_depth();
+                    self.print_expr(elem);
+                }
+                self.rise();
+            }
+            ExprKind::Index { base, index } => {
+                eprintln!("Index of:");
+                self.dive();
+                self.print_depth();
+                self.print_expr(base);
+                self.print_depth();
+                self.print_expr(index);
+                self.rise();
+            }
             ExprKind::Binary { op, lhs, rhs } => self.print_binary_expr(op, lhs, rhs),
+            ExprKind::Unary { op, operand } => self.print_unary_expr(op, operand),
             ExprKind::Lit(f) => self.print_literal(f),
             ExprKind::Var(v) => self.print_var(v),
             ExprKind::If {
@@ -137,9 +219,89 @@ impl<'s> PrinterState<'s> {
                 else_body,
             } => self.print_if(cond, if_body, else_body),
             ExprKind::Decl(vd) => self.print_assignment(vd),
+            ExprKind::Destructure { pat, ty, value, .. } => {
+                match ty {
+                    Some(ty) => eprintln!("Bind {}: {} with:", pattern_string(pat), ty),
+                    None => eprintln!("Bind {} with:", pattern_string(pat)),
+                }
+                self.dive();
+                self.print_depth();
+                self.print_expr(value);
+                self.rise();
+            }
             ExprKind::Let { bound, let_body } => self.print_let(bound, let_body),
             ExprKind::While { cond, while_body } => self.print_while(cond, while_body),
+            ExprKind::Loop(loop_body) => self.print_loop(loop_body),
+            ExprKind::For { range, for_body } => self.print_for(range, for_body),
+            ExprKind::Match { scrutinee, arms } => self.print_match(scrutinee, arms),
+            ExprKind::Break(None) => eprintln!("Break"),
+            ExprKind::Break(Some(value)) => {
+                eprintln!("Break with:");
+                self.dive();
+                self.print_depth();
+                self.print_expr(value);
+                self.rise();
+            }
+            ExprKind::Continue => eprintln!("Continue"),
+            ExprKind::Return(value) => self.print_return(value),
+            ExprKind::Error => eprintln!("Error"),
+        }
+    }
+
+    fn print_loop(&mut self, body: &Expr<'s>) {
+        eprintln!("Loop:");
+        self.dive();
+        self.print_depth();
+        self.print_expr(body);
+        self.rise();
+    }
+
+    fn print_for(&mut self, range: &ForRange<'s>, body: &Expr<'s>) {
+        eprintln!("For {:?} in:", range.var.span);
+        self.dive();
+        self.print_depth();
+        self.print_expr(&range.start);
+        self.print_depth();
+        self.print_expr(&range.end);
+        if let Some(step) = &range.step {
+            self.print_depth();
+            eprint!("step ");
+            self.print_expr(step);
+        }
+        self.rise();
+        self.print_depth();
+        eprintln!("Do:");
+        self.dive();
+        self.print_depth();
+        self.print_expr(body);
+        self.rise();
+    }
+
+    fn print_match(&mut self, scrutinee: &Expr<'s>, arms: &[MatchArm<'s>]) {
+        eprintln!("Match:");
+        self.dive();
+        self.print_depth();
+        self.print_expr(scrutinee);
+        self.rise();
+        for arm in arms {
+            self.print_depth();
+            eprintln!("Case {}:", pattern_string(&arm.pat));
+            self.dive();
+            self.print_depth();
+            self.print_expr(&arm.body);
+            self.rise();
+        }
+    }
+
+    fn print_struct_lit(&mut self, name: &TokenData<'s>, fields: &[FieldInit<'s>]) {
+        eprintln!("Struct {:?} with:", name.span);
+        self.dive();
+        for field in fields {
+            self.print_depth();
+            eprint!("{:?}: ", field.name.span);
+            self.print_expr(&field.value);
         }
+        self.rise();
     }
 
     fn print_while(&mut self, cond: &Expr<'s>, body: &Expr<'s>) {
@@ -156,8 +318,19 @@ impl<'s> PrinterState<'s> {
         self.rise();
     }
 
+    fn print_return(&mut self, value: &Expr<'s>) {
+        eprintln!("Return");
+        self.dive();
+        self.print_depth();
+        self.print_expr(value);
+        self.rise();
+    }
+
     fn print_assignment(&mut self, decl: &VarDecl<'s>) {
-        eprintln!("Bind {:?} with:", decl.bound.span);
+        match &decl.ty {
+            Some(ty) => eprintln!("Bind {:?}: {} with:", decl.bound.span, ty),
+            None => eprintln!("Bind {:?} with:", decl.bound.span),
+        }
         self.dive();
         self.print_depth();
         self.print_expr(&decl.value);
@@ -244,11 +417,35 @@ impl<'s> PrinterState<'s> {
         self.rise();
     }
 
-    fn print_literal(&mut self, lit: &f64) {
-        eprintln!("Literal {}", lit);
+    fn print_unary_expr(&mut self, op: &UnaryOp, operand: &Expr<'s>) {
+        eprintln!("Unary {:?}", op);
+        self.dive();
+        self.print_depth();
+        self.print_expr(operand);
+        self.rise();
+    }
+
+    fn print_literal(&mut self, lit: &Lit) {
+        eprintln!("Literal {:?}", lit);
     }
 
     fn print_var(&mut self, td: &TokenData<'s>) {
         eprintln!("Var {:?}", td.span)
     }
 }
+
+fn pattern_string(pat: &Pattern) -> String {
+    match &pat.kind {
+        PatternKind::Wildcard => String::from("_"),
+        PatternKind::Lit(lit) => format!("{:?}", lit),
+        PatternKind::Bind(name) => format!("{:?}", name.span),
+        PatternKind::Variant { name, args } => {
+            let args: Vec<_> = args.iter().map(pattern_string).collect();
+            format!("{:?}({})", name.span, args.join(", "))
+        }
+        PatternKind::Tuple(elems) => {
+            let elems: Vec<_> = elems.iter().map(pattern_string).collect();
+            format!("({})", elems.join(", "))
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use crate::ast::token::SrcSpan;
+use crate::ast::{
+    EnumTable, Expr, ExprKind, ItemKind, Lit, MatchArm, Module, Pattern, PatternKind, Type,
+    TypeKind, TypeTable,
+};
+use crate::ast_pass::ModulePass;
+use crate::diag::{Diagnostic, ToDiagnostic};
+
+// Checks every `match` has an arm for any value
+// and that each arm can match something the arms before it do not,
+// the pattern of a `val` must match any value
+// runs after type checking
+// arms are compared as rows of a pattern matrix, an arm is reachable
+// when some value matches it but no row above it
+pub struct AstMatchChecker<'a> {
+    types: &'a TypeTable,
+    enums: EnumTable,
+    errs: Vec<AstMatchErr>,
+    warns: Vec<AstMatchWarn>,
+}
+
+// a pattern reduced to what decides which values it matches
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Ctor(Ctor, Vec<Pat>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ctor {
+    Bool(bool),
+    Variant { tag: u32, name: String },
+    // the only constructor of a tuple type
+    Tuple,
+    // other literals, no set of them covers every value
+    Lit(Lit),
+}
+
+#[derive(Debug)]
+pub enum AstMatchErr {
+    NonExhaustive {
+        scrutinee: SrcSpan,
+        ty: Type,
+        // a value no arm matches
+        missing: String,
+    },
+    // a `val` pattern some value does not match
+    Refutable {
+        pat: SrcSpan,
+        missing: String,
+    },
+}
+
+impl ToDiagnostic for AstMatchErr {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            AstMatchErr::NonExhaustive {
+                scrutinee,
+                ty,
+                missing,
+            } => {
+                let msg = format!("non-exhaustive `match` on `{}`", ty);
+                Diagnostic::error("E0401", msg)
+                    .with_primary(*scrutinee, format!("`{}` is not matched", missing))
+                    .with_note(format!("add a `case {} =>` arm", missing))
+            }
+            AstMatchErr::Refutable { pat, missing } => {
+                Diagnostic::error("E0402", String::from("refutable pattern in `val`"))
+                    .with_primary(*pat, format!("`{}` is not matched", missing))
+                    .with_note(String::from(
+                        "use `match` or `if let` to handle other values",
+                    ))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AstMatchWarn {
+    // the arms before it already match everything it does
+    UnreachableArm { arm: SrcSpan, prev: SrcSpan },
+}
+
+impl ToDiagnostic for AstMatchWarn {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            AstMatchWarn::UnreachableArm { arm, prev } => {
+                Diagnostic::warning("W0401", String::from("unreachable `case`"))
+                    .with_primary(*arm, String::from("this is never matched"))
+                    .with_secondary(*prev, String::from("already matched here"))
+            }
+        }
+    }
+}
+
+pub type MatchCheckResult = Result<Vec<AstMatchWarn>, Vec<AstMatchErr>>;
+
+impl<'a> AstMatchChecker<'a> {
+    pub fn new(types: &'a TypeTable) -> Self {
+        Self {
+            types,
+            enums: EnumTable::default(),
+            errs: Vec::new(),
+            warns: Vec::new(),
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr) {
+        match &*expr.kind {
+            ExprKind::Match { scrutinee, arms } => {
+                self.check_expr(scrutinee);
+                for arm in arms {
+                    self.check_expr(&arm.body);
+                }
+                self.check_arms(scrutinee, arms);
+            }
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            }
+            ExprKind::Unary { operand, .. } => self.check_expr(operand),
+            ExprKind::Call { callee, args } => {
+                self.check_expr(callee);
+                for arg in args {
+                    self.check_expr(arg);
+                }
+            }
+            ExprKind::StructLit { fields, .. } => {
+                for field in fields {
+                    self.check_expr(&field.value);
+                }
+            }
+            ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => {
+                self.check_expr(base)
+            }
+            ExprKind::ArrayLit(elems) | ExprKind::TupleLit(elems) => {
+                for elem in elems {
+                    self.check_expr(elem);
+                }
+            }
+            ExprKind::Index { base, index } => {
+                self.check_expr(base);
+                self.check_expr(index);
+            }
+            ExprKind::If {
+                cond,
+                if_body,
+                else_body,
+            } => {
+                self.check_expr(cond);
+                self.check_expr(if_body);
+                self.check_expr(else_body);
+            }
+            ExprKind::Block(block) => {
+                for sub in block {
+                    self.check_expr(sub);
+                }
+            }
+            ExprKind::Decl(vd) => self.check_expr(&vd.value),
+            ExprKind::Destructure { pat, value, .. } => {
+                self.check_expr(value);
+                self.check_irrefutable(value, pat);
+            }
+            ExprKind::Let { bound, let_body } => {
+                for decl in bound {
+                    self.check_expr(decl);
+                }
+                self.check_expr(let_body);
+            }
+            ExprKind::While { cond, while_body } => {
+                self.check_expr(cond);
+                self.check_expr(while_body);
+            }
+            ExprKind::Loop(loop_body) => self.check_expr(loop_body),
+            ExprKind::For { range, for_body } => {
+                self.check_expr(&range.start);
+                self.check_expr(&range.end);
+                if let Some(step) = &range.step {
+                    self.check_expr(step);
+                }
+                self.check_expr(for_body);
+            }
+            ExprKind::Return(value) | ExprKind::Break(Some(value)) => self.check_expr(value),
+            ExprKind::Var(_)
+            | ExprKind::Lit(_)
+            | ExprKind::Break(None)
+            | ExprKind::Continue
+            | ExprKind::Error => (),
+        }
+    }
+
+    fn check_arms(&mut self, scrutinee: &Expr, arms: &[MatchArm]) {
+        let ty = match self.types.lookup(&scrutinee.nid) {
+            Some(ty) => ty.clone(),
+            // untyped nodes were already reported
+            None => return,
+        };
+        let tys = [ty.clone()];
+        let rows: Vec<Vec<Pat>> = arms.iter().map(|arm| vec![self.lower(&arm.pat)]).collect();
+
+        for (idx, arm) in arms.iter().enumerate().skip(1) {
+            if self.witness(&rows[..idx], &tys, &rows[idx]).is_some() {
+                continue;
+            }
+            // the first arm by which every value of this one is taken
+            let prev = (0..idx)
+                .find(|&j| self.witness(&rows[..=j], &tys, &rows[idx]).is_none())
+                .unwrap_or(idx - 1);
+            self.warns.push(AstMatchWarn::UnreachableArm {
+                arm: arm.pat.span,
+                prev: arms[prev].pat.span,
+            });
+        }
+
+        if let Some(missing) = self.witness(&rows, &tys, &[Pat::Wild]) {
+            self.errs.push(AstMatchErr::NonExhaustive {
+                scrutinee: scrutinee.span,
+                missing: render(&missing[0]),
+                ty,
+            });
+        }
+    }
+
+    fn check_irrefutable(&mut self, value: &Expr, pat: &Pattern) {
+        let ty = match self.types.lookup(&value.nid) {
+            Some(ty) => ty.clone(),
+            None => return,
+        };
+        let rows = [vec![self.lower(pat)]];
+        if let Some(missing) = self.witness(&rows, &[ty], &[Pat::Wild]) {
+            self.errs.push(AstMatchErr::Refutable {
+                pat: pat.span,
+                missing: render(&missing[0]),
+            });
+        }
+    }
+
+    fn lower(&self, pat: &Pattern) -> Pat {
+        match &pat.kind {
+            PatternKind::Wildcard => Pat::Wild,
+            PatternKind::Bind(name) => match self.enums.variant(name.span) {
+                Some(variant) => Pat::Ctor(variant_ctor(variant.tag, &variant.name), Vec::new()),
+                None => Pat::Wild,
+            },
+            PatternKind::Lit(Lit::Bool(b)) => Pat::Ctor(Ctor::Bool(*b), Vec::new()),
+            PatternKind::Lit(lit) => Pat::Ctor(Ctor::Lit(lit.clone()), Vec::new()),
+            PatternKind::Variant { name, args } => match self.enums.variant(name.span) {
+                Some(variant) => {
+                    let args = args.iter().map(|arg| self.lower(arg)).collect();
+                    Pat::Ctor(variant_ctor(variant.tag, &variant.name), args)
+                }
+                // unknown variants were reported by name resolution
+                None => Pat::Wild,
+            },
+            PatternKind::Tuple(elems) => {
+                let elems = elems.iter().map(|elem| self.lower(elem)).collect();
+                Pat::Ctor(Ctor::Tuple, elems)
+            }
+        }
+    }
+
+    // every constructor of a type with its payload types,
+    // None when no finite set of them covers all values
+    fn all_ctors(&self, ty: &Type) -> Option<Vec<(Ctor, Vec<Type>)>> {
+        match ty.kind() {
+            TypeKind::Bool => Some(vec![
+                (Ctor::Bool(true), Vec::new()),
+                (Ctor::Bool(false), Vec::new()),
+            ]),
+            TypeKind::Named(name) => {
+                let variants = self.enums.variants(name)?;
+                let ctors = variants
+                    .iter()
+                    .map(|v| (variant_ctor(v.tag, &v.name), v.payload.clone()))
+                    .collect();
+                Some(ctors)
+            }
+            TypeKind::Tuple(elems) => Some(vec![(Ctor::Tuple, elems.clone())]),
+            _ => None,
+        }
+    }
+
+    fn payload_types(&self, ty: &Type, ctor: &Ctor) -> Vec<Type> {
+        self.all_ctors(ty)
+            .and_then(|all| all.into_iter().find(|(c, _)| c == ctor))
+            .map(|(_, payload)| payload)
+            .unwrap_or_default()
+    }
+
+    // values matching `q` that no row matches, as the patterns of one of them
+    fn witness(&self, rows: &[Vec<Pat>], tys: &[Type], q: &[Pat]) -> Option<Vec<Pat>> {
+        let (head, rest) = match q.split_first() {
+            Some(split) => split,
+            None if rows.is_empty() => return Some(Vec::new()),
+            None => return None,
+        };
+        let ty = &tys[0];
+
+        if let Pat::Ctor(ctor, args) = head {
+            return self.witness_ctor(rows, tys, ctor, args, rest);
+        }
+
+        let used: Vec<&Ctor> = rows
+            .iter()
+            .filter_map(|row| match &row[0] {
+                Pat::Ctor(ctor, _) => Some(ctor),
+                Pat::Wild => None,
+            })
+            .collect();
+
+        let all = self.all_ctors(ty);
+        if let Some(all) = &all {
+            if all.iter().all(|(ctor, _)| used.contains(&ctor)) {
+                // every constructor appears, one of them must be missing something
+                return all.iter().find_map(|(ctor, payload)| {
+                    let args = vec![Pat::Wild; payload.len()];
+                    self.witness_ctor(rows, tys, ctor, &args, rest)
+                });
+            }
+        }
+
+        // rows starting with a constructor cannot match a missing one
+        let default: Vec<Vec<Pat>> = rows
+            .iter()
+            .filter(|row| matches!(row[0], Pat::Wild))
+            .map(|row| row[1..].to_vec())
+            .collect();
+        let mut found = self.witness(&default, &tys[1..], rest)?;
+
+        // naming a missing constructor helps more than `_`, unless none is matched
+        let missing = all.filter(|_| !used.is_empty()).and_then(|all| {
+            let (ctor, payload) = all.into_iter().find(|(ctor, _)| !used.contains(&ctor))?;
+            Some(Pat::Ctor(ctor, vec![Pat::Wild; payload.len()]))
+        });
+        found.insert(0, missing.unwrap_or(Pat::Wild));
+        Some(found)
+    }
+
+    // `witness` of the rows a value built by `ctor` can match
+    fn witness_ctor(
+        &self,
+        rows: &[Vec<Pat>],
+        tys: &[Type],
+        ctor: &Ctor,
+        args: &[Pat],
+        rest: &[Pat],
+    ) -> Option<Vec<Pat>> {
+        let arity = args.len();
+        let specialized: Vec<Vec<Pat>> = rows
+            .iter()
+            .filter_map(|row| {
+                let mut sub = match &row[0] {
+                    Pat::Ctor(c, sub) if c == ctor => sub.clone(),
+                    Pat::Ctor(..) => return None,
+                    Pat::Wild => vec![Pat::Wild; arity],
+                };
+                sub.extend_from_slice(&row[1..]);
+                Some(sub)
+            })
+            .collect();
+
+        let mut sub_tys = self.payload_types(&tys[0], ctor);
+        sub_tys.extend_from_slice(&tys[1..]);
+        let mut sub_q = args.to_vec();
+        sub_q.extend_from_slice(rest);
+
+        let mut found = self.witness(&specialized, &sub_tys, &sub_q)?;
+        let after = found.split_off(arity);
+        let mut w = vec![Pat::Ctor(ctor.clone(), found)];
+        w.extend(after);
+        Some(w)
+    }
+}
+
+fn variant_ctor(tag: u32, name: &str) -> Ctor {
+    Ctor::Variant {
+        tag,
+        name: String::from(name),
+    }
+}
+
+// shown to the user as the value no arm matches
+fn render(pat: &Pat) -> String {
+    match pat {
+        Pat::Wild => String::from("_"),
+        Pat::Ctor(Ctor::Bool(b), _) => b.to_string(),
+        Pat::Ctor(Ctor::Variant { name, .. }, args) if args.is_empty() => name.clone(),
+        Pat::Ctor(Ctor::Variant { name, .. }, args) => {
+            let args: Vec<String> = args.iter().map(render).collect();
+            format!("{}({})", name, args.join(", "))
+        }
+        Pat::Ctor(Ctor::Tuple, elems) => {
+            let elems: Vec<String> = elems.iter().map(render).collect();
+            format!("({})", elems.join(", "))
+        }
+        // never missing, other literals are covered by `_`
+        Pat::Ctor(Ctor::Lit(_), _) => String::from("_"),
+    }
+}
+
+impl<'a, 's> ModulePass<'s> for AstMatchChecker<'a> {
+    type Output = MatchCheckResult;
+
+    fn run_pass(mut self, m: &Module<'s>) -> Self::Output {
+        self.enums = EnumTable::new(m);
+
+        for item in &m.body {
+            if let ItemKind::Func(f) = &item.kind {
+                self.check_expr(&f.body);
+            }
+        }
+
+        if self.errs.is_empty() {
+            Ok(self.warns)
+        } else {
+            Err(self.errs)
+        }
+    }
+}
+
+#[test]
+fn test_missing_bool_case() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun f(b: bool)\n    match b\n    case true => 1\n    end\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0401");
+    assert_eq!(diags[0].msg, "non-exhaustive `match` on `bool`");
+}
+
+#[test]
+fn test_case_after_wildcard_warns() {
+    use crate::cli::check::expect_checked;
+
+    let src = "mod m\nfun f(n: i64)\n    match n\n    case 1 => 10\n    case _ => 0\n    case 2 => 20\n    end\nend\n";
+    let checked = expect_checked(src);
+
+    assert_eq!(checked.warnings.len(), 1);
+    assert_eq!(checked.warnings[0].code, "W0401");
+}
+
+#[test]
+fn test_missing_variant_case() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nenum S A(f64) | B(f64, f64) end\nenum O Some(S) | None end\nfun f(o: O): f64\n    match o\n    case Some(A(x)) => x\n    case None => 0.0\n    end\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0401");
+    assert_eq!(diags[0].msg, "non-exhaustive `match` on `O`");
+}
+
+#[test]
+fn test_refutable_val_pattern() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun f(p: (i64, bool)): i64\n    val (n, true) = p\n    n\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0402");
+    assert_eq!(diags[0].labels[0].msg, "`(_, false)` is not matched");
+}
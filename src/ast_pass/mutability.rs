@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+
+use crate::ast::ops::BinaryOp;
+use crate::ast::symbol::{ModSymTable, SymId};
+use crate::ast::token::{SrcSpan, TokenData};
+use crate::ast::{
+    Expr, ExprKind, Function, Item, ItemKind, Module, MutKind, NodeId, Pattern, PatternKind,
+};
+use crate::ast_pass::ModulePass;
+use crate::diag::{Diagnostic, ToDiagnostic};
+
+// what a symbol was declared as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BindingKind {
+    Mutable,
+    Const,
+    Arg,
+    Function,
+    LoopVar,
+    Pattern,
+    Variant,
+}
+
+// how much of a binding an assignment writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignPart {
+    Whole,
+    Field,
+    Element,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Binding {
+    kind: BindingKind,
+    decl: SrcSpan,
+}
+
+// Checks every assignment targets a `val mut` binding
+// runs after name resolution
+pub struct AstMutabilityChecker<'a> {
+    mst: &'a ModSymTable,
+    bindings: HashMap<SymId, Binding>,
+}
+
+#[derive(Debug)]
+pub enum AstMutabilityErr<'s> {
+    AssignToImmutable {
+        target: TokenData<'s>,
+        kind: BindingKind,
+        decl: SrcSpan,
+        // `p.x = ...` and `a[i] = ...` write part of `p` and `a`
+        part: AssignPart,
+    },
+    // left hand side is not a variable
+    InvalidAssignTarget(SrcSpan),
+}
+
+impl<'s> ToDiagnostic for AstMutabilityErr<'s> {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            AstMutabilityErr::AssignToImmutable {
+                target,
+                kind,
+                decl,
+                part,
+            } => {
+                let name = target.span;
+                let (mut msg, decl_msg) = match kind {
+                    BindingKind::Arg => (
+                        format!("cannot assign to function argument `{}`", name),
+                        String::from("arguments are constant"),
+                    ),
+                    BindingKind::Function => (
+                        format!("cannot assign to function `{}`", name),
+                        String::from("function declared here"),
+                    ),
+                    BindingKind::LoopVar => (
+                        format!("cannot assign to loop variable `{}`", name),
+                        String::from("counted by the `for` loop"),
+                    ),
+                    BindingKind::Pattern => (
+                        format!("cannot assign to pattern binding `{}`", name),
+                        String::from("bound by this `case`"),
+                    ),
+                    BindingKind::Variant => (
+                        format!("cannot assign to enum variant `{}`", name),
+                        String::from("variant declared here"),
+                    ),
+                    _ => (
+                        format!("cannot assign twice to immutable variable `{}`", name),
+                        String::from("declared without `mut`"),
+                    ),
+                };
+                if *part != AssignPart::Whole {
+                    let what = match kind {
+                        BindingKind::Arg => "function argument",
+                        BindingKind::LoopVar => "loop variable",
+                        BindingKind::Pattern => "pattern binding",
+                        _ => "immutable variable",
+                    };
+                    let of = match part {
+                        AssignPart::Element => "an element",
+                        _ => "a field",
+                    };
+                    msg = format!("cannot assign to {} of {} `{}`", of, what, name);
+                }
+
+                let d = Diagnostic::error("E0203", msg)
+                    .with_primary(target.src_span(), String::from("cannot assign"))
+                    .with_secondary(*decl, decl_msg);
+
+                match kind {
+                    BindingKind::Const => d.with_note(format!(
+                        "declare it with `val mut {}` to allow assignment",
+                        name
+                    )),
+                    BindingKind::Arg => d.with_note(format!(
+                        "copy it into a local first: `val mut {0} = {0}`",
+                        name
+                    )),
+                    _ => d,
+                }
+            }
+            AstMutabilityErr::InvalidAssignTarget(span) => Diagnostic::error(
+                "E0204",
+                String::from("invalid left hand side of assignment"),
+            )
+            .with_primary(*span, String::from("cannot assign to this expression")),
+        }
+    }
+}
+
+pub type MutabilityResult<'s> = Result<(), Vec<AstMutabilityErr<'s>>>;
+
+impl<'a> AstMutabilityChecker<'a> {
+    pub fn new(mst: &'a ModSymTable) -> Self {
+        Self {
+            mst,
+            bindings: HashMap::new(),
+        }
+    }
+
+    fn record(&mut self, nid: &NodeId, kind: BindingKind, decl: SrcSpan) {
+        // unresolved nodes were already reported by name resolution
+        if let Some(sid) = self.mst.lookup(nid) {
+            self.bindings.insert(*sid, Binding { kind, decl });
+        }
+    }
+
+    // variants named by a pattern were not given a symbol
+    fn record_pattern(&mut self, pat: &Pattern, kind: BindingKind) {
+        match &pat.kind {
+            PatternKind::Bind(name) => self.record(&pat.nid, kind, name.src_span()),
+            PatternKind::Variant { args, .. } | PatternKind::Tuple(args) => {
+                for arg in args {
+                    self.record_pattern(arg, kind);
+                }
+            }
+            PatternKind::Wildcard | PatternKind::Lit(_) => (),
+        }
+    }
+
+    fn check_item<'s>(&mut self, item: &Item<'s>, errs: &mut Vec<AstMutabilityErr<'s>>) {
+        if let ItemKind::Func(f) = &item.kind {
+            for arg in &f.proto.args {
+                self.record(&arg.nid, BindingKind::Arg, arg.name.src_span());
+            }
+            self.check_expr(&f.body, errs);
+        }
+    }
+
+    fn check_expr<'s>(&mut self, expr: &Expr<'s>, errs: &mut Vec<AstMutabilityErr<'s>>) {
+        match &*expr.kind {
+            ExprKind::Decl(vd) => {
+                self.check_expr(&vd.value, errs);
+                let kind = match vd.mutable {
+                    MutKind::Mutable => BindingKind::Mutable,
+                    MutKind::Const => BindingKind::Const,
+                };
+                self.record(&expr.nid, kind, vd.bound.src_span());
+            }
+            ExprKind::Destructure {
+                mutable,
+                pat,
+                value,
+                ..
+            } => {
+                self.check_expr(value, errs);
+                let kind = match mutable {
+                    MutKind::Mutable => BindingKind::Mutable,
+                    MutKind::Const => BindingKind::Const,
+                };
+                self.record_pattern(pat, kind);
+            }
+            ExprKind::Binary {
+                op: BinaryOp::Assign,
+                lhs,
+                rhs,
+            } => {
+                self.check_expr(rhs, errs);
+                self.check_assign_target(lhs, errs);
+            }
+            ExprKind::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs, errs);
+                self.check_expr(rhs, errs);
+            }
+            ExprKind::Unary { operand, .. } => self.check_expr(operand, errs),
+            ExprKind::StructLit { fields, .. } => {
+                for field in fields {
+                    self.check_expr(&field.value, errs);
+                }
+            }
+            ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => {
+                self.check_expr(base, errs)
+            }
+            ExprKind::ArrayLit(elems) | ExprKind::TupleLit(elems) => {
+                for elem in elems {
+                    self.check_expr(elem, errs);
+                }
+            }
+            ExprKind::Index { base, index } => {
+                self.check_expr(base, errs);
+                self.check_expr(index, errs);
+            }
+            ExprKind::Call { callee, args } => {
+                self.check_expr(callee, errs);
+                for arg in args {
+                    self.check_expr(arg, errs);
+                }
+            }
+            ExprKind::If {
+                cond,
+                if_body,
+                else_body,
+            } => {
+                self.check_expr(cond, errs);
+                self.check_expr(if_body, errs);
+                self.check_expr(else_body, errs);
+            }
+            ExprKind::Block(block) => {
+                for sub in block {
+                    self.check_expr(sub, errs);
+                }
+            }
+            ExprKind::Let { bound, let_body } => {
+                for decl in bound {
+                    self.check_expr(decl, errs);
+                }
+                self.check_expr(let_body, errs);
+            }
+            ExprKind::While { cond, while_body } => {
+                self.check_expr(cond, errs);
+                self.check_expr(while_body, errs);
+            }
+            ExprKind::Loop(loop_body) => self.check_expr(loop_body, errs),
+            ExprKind::For { range, for_body } => {
+                self.check_expr(&range.start, errs);
+                self.check_expr(&range.end, errs);
+                if let Some(step) = &range.step {
+                    self.check_expr(step, errs);
+                }
+                self.record(&range.var_nid, BindingKind::LoopVar, range.var.src_span());
+                self.check_expr(for_body, errs);
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.check_expr(scrutinee, errs);
+                for arm in arms {
+                    self.record_pattern(&arm.pat, BindingKind::Pattern);
+                    self.check_expr(&arm.body, errs);
+                }
+            }
+            ExprKind::Return(value) | ExprKind::Break(Some(value)) => self.check_expr(value, errs),
+            ExprKind::Var(_)
+            | ExprKind::Lit(_)
+            | ExprKind::Break(None)
+            | ExprKind::Continue
+            | ExprKind::Error => (),
+        }
+    }
+
+    fn check_assign_target<'s>(&self, lhs: &Expr<'s>, errs: &mut Vec<AstMutabilityErr<'s>>) {
+        // a field or element write needs the variable holding the whole value to be mutable
+        let mut root = lhs;
+        while let ExprKind::Field { base, .. }
+        | ExprKind::TupleField { base, .. }
+        | ExprKind::Index { base, .. } = &*root.kind
+        {
+            root = base;
+        }
+
+        let target = match &*root.kind {
+            ExprKind::Var(td) => td,
+            _ => {
+                errs.push(AstMutabilityErr::InvalidAssignTarget(lhs.span));
+                return;
+            }
+        };
+
+        let binding = self
+            .mst
+            .lookup(&root.nid)
+            .and_then(|sid| self.bindings.get(sid));
+
+        if let Some(binding) = binding {
+            if binding.kind != BindingKind::Mutable {
+                errs.push(AstMutabilityErr::AssignToImmutable {
+                    target: *target,
+                    kind: binding.kind,
+                    decl: binding.decl,
+                    part: match &*lhs.kind {
+                        ExprKind::Field { .. } | ExprKind::TupleField { .. } => AssignPart::Field,
+                        ExprKind::Index { .. } => AssignPart::Element,
+                        _ => AssignPart::Whole,
+                    },
+                });
+            }
+        }
+    }
+}
+
+impl<'a, 's> ModulePass<'s> for AstMutabilityChecker<'a> {
+    type Output = MutabilityResult<'s>;
+
+    fn run_pass(mut self, m: &Module<'s>) -> Self::Output {
+        let mut errs = Vec::new();
+
+        // functions can be referenced before they are defined
+        for item in &m.body {
+            match &item.kind {
+                ItemKind::Func(Function { proto, .. }) | ItemKind::Extern(proto) => {
+                    self.record(&item.nid, BindingKind::Function, proto.name.src_span())
+                }
+                ItemKind::Enum(def) => {
+                    for variant in &def.variants {
+                        let span = variant.name.src_span();
+                        self.record(&variant.nid, BindingKind::Variant, span);
+                    }
+                }
+                ItemKind::Struct(_) | ItemKind::Error => (),
+            }
+        }
+
+        for item in &m.body {
+            self.check_item(item, &mut errs);
+        }
+
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+}
+
+#[test]
+fn test_assign_points_at_declaration() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m
+fun f(a: i64): i64
+    val x = 1
+    x = 2
+    a = 3
+    f = f
+    a
+end
+";
+    let diags = expect_errors(src);
+
+    // (message, line of the assignment, line and column of the declaration)
+    let expected = [
+        ("cannot assign twice to immutable variable `x`", 4, (3, 9)),
+        ("cannot assign to function argument `a`", 5, (2, 7)),
+        ("cannot assign to function `f`", 6, (2, 5)),
+    ];
+    assert_eq!(diags.len(), expected.len());
+    for (diag, (msg, line, decl)) in diags.iter().zip(expected.iter()) {
+        assert_eq!(diag.code, "E0203");
+        assert_eq!(diag.msg, *msg);
+
+        let primary = diag.labels.iter().find(|l| l.primary).unwrap();
+        assert_eq!(primary.span.start.line, *line);
+
+        let secondary = diag.labels.iter().find(|l| !l.primary).unwrap();
+        let start = secondary.span.start;
+        assert_eq!((start.line, start.column), *decl);
+    }
+}
+
+#[test]
+fn test_element_write_needs_mutable_array() {
+    use crate::cli::check::{expect_checked, expect_errors};
+
+    let src = "mod m\nfun f(): f64\n    val a = [1.0]\n    a[0] = 2.0\n    a[0]\nend\n";
+    let diags = expect_errors(src);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0203");
+    assert_eq!(
+        diags[0].msg,
+        "cannot assign to an element of immutable variable `a`"
+    );
+
+    let src = "mod m\nfun f(): f64\n    val mut a = [1.0]\n    a[0] = 2.0\n    a[0]\nend\n";
+    expect_checked(src);
+}
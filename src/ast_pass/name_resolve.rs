@@ -1,20 +1,251 @@
-use crate::ast::symbol::{ModSymTable, ScopedInsertErr, ScopedSymbolStack};
-use crate::ast::{Expr, ExprKind, Function, Item, ItemKind, Module};
+use std::collections::HashMap;
+
+use crate::ast::symbol::{ModSymTable, ScopedInsertErr, ScopedInsertResult, ScopedSymbolStack};
+use crate::ast::token::{SrcSpan, TokenData};
+use crate::ast::{
+    Builtin, EnumDef, Expr, ExprKind, FieldInit, FuncProto, Function, Item, ItemKind, Module,
+    Pattern, PatternKind, StructDef, Type, TypeKind,
+};
 use crate::ast_pass::ModulePass;
+use crate::diag::{Diagnostic, ToDiagnostic};
 
 pub struct AstNameResolver<'s> {
     scopes: ScopedSymbolStack<'s>,
-    errs: Vec<ScopedInsertErr>,
+    errs: Vec<AstResolutionErr<'s>>,
+    warns: Vec<AstResolutionWarn<'s>>,
+    // lint for bindings that hide an outer name
+    warn_shadowing: bool,
+    // keywords of the loops around the expression being resolved
+    loops: Vec<&'static str>,
+    // struct and enum names live apart from values
+    types: HashMap<&'s str, TypeInfo<'s>>,
+    // payload length of every enum variant
+    variants: HashMap<&'s str, usize>,
+}
+
+enum TypeInfo<'s> {
+    Struct {
+        name: TokenData<'s>,
+        fields: Vec<(TokenData<'s>, Type)>,
+    },
+    Enum {
+        name: TokenData<'s>,
+        // of every variant
+        payloads: Vec<Type>,
+    },
+}
+
+impl<'s> TypeInfo<'s> {
+    fn name(&self) -> TokenData<'s> {
+        match self {
+            TypeInfo::Struct { name, .. } | TypeInfo::Enum { name, .. } => *name,
+        }
+    }
+
+    // types stored by value inside this one
+    fn member_types(&self) -> Vec<&Type> {
+        let direct: Vec<&Type> = match self {
+            TypeInfo::Struct { fields, .. } => fields.iter().map(|(_, ty)| ty).collect(),
+            TypeInfo::Enum { payloads, .. } => payloads.iter().collect(),
+        };
+        let mut members = Vec::new();
+        for ty in direct {
+            push_inline_types(ty, &mut members);
+        }
+        members
+    }
+}
+
+// the elements of a tuple are stored inline, unlike those of an array
+fn push_inline_types<'t>(ty: &'t Type, out: &mut Vec<&'t Type>) {
+    match ty.kind() {
+        TypeKind::Tuple(elems) => {
+            for elem in elems {
+                push_inline_types(elem, out);
+            }
+        }
+        _ => out.push(ty),
+    }
 }
 
-pub enum AstResolutionErr {
-    Redefinition,
+#[derive(Debug)]
+pub enum AstResolutionErr<'s> {
+    Redefinition {
+        name: TokenData<'s>,
+        prev: SrcSpan,
+    },
+    // use of a name that is not visible from this scope
+    Undefined {
+        name: &'s str,
+        loc: SrcSpan,
+        suggestion: Option<&'s str>,
+    },
+    // `break` or `continue` with no loop to jump in
+    OutsideLoop {
+        kw: &'static str,
+        span: SrcSpan,
+    },
+    // only `loop` has a value
+    BreakWithValue {
+        loop_kw: &'static str,
+        span: SrcSpan,
+    },
+    // annotation or literal naming a struct that does not exist
+    UndefinedType {
+        name: String,
+        // the annotated name or the literal
+        used_by: SrcSpan,
+    },
+    // a struct or enum that contains itself has no size
+    RecursiveType {
+        kw: &'static str,
+        name: TokenData<'s>,
+    },
+    // `{ ... }` after an enum name
+    NotAStruct {
+        name: TokenData<'s>,
+    },
+    UnknownField {
+        ty: &'s str,
+        field: TokenData<'s>,
+    },
+    MissingFields {
+        ty: &'s str,
+        fields: Vec<&'s str>,
+        span: SrcSpan,
+    },
+    UndefinedVariant {
+        name: TokenData<'s>,
+    },
+    // a variant pattern needs one pattern per payload value
+    PatternArity {
+        variant: TokenData<'s>,
+        expected: usize,
+        found: usize,
+        span: SrcSpan,
+    },
+    CompilerBug(String),
     // ReturnBeforeBlockEnd,
 }
-pub type NameResolutionResult = Result<ModSymTable, Vec<ScopedInsertErr>>;
+
+impl<'s> ToDiagnostic for AstResolutionErr<'s> {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            AstResolutionErr::Redefinition { name, prev } => {
+                let msg = format!("`{}` is defined more than once", name.span);
+                Diagnostic::error("E0201", msg)
+                    .with_primary(name.src_span(), String::from("redefined here"))
+                    .with_secondary(*prev, String::from("first defined here"))
+            }
+            AstResolutionErr::Undefined {
+                name,
+                loc,
+                suggestion,
+            } => {
+                let msg = format!("cannot find `{}` in this scope", name);
+                let d = Diagnostic::error("E0202", msg)
+                    .with_primary(*loc, String::from("not found in this scope"));
+                match suggestion {
+                    Some(similar) => d.with_note(format!("did you mean `{}`?", similar)),
+                    None => d,
+                }
+            }
+            AstResolutionErr::OutsideLoop { kw, span } => {
+                Diagnostic::error("E0205", format!("`{}` outside of a loop", kw))
+                    .with_primary(*span, format!("cannot `{}` here", kw))
+            }
+            AstResolutionErr::BreakWithValue { loop_kw, span } => {
+                let msg = format!("cannot `break` with a value from a `{}` loop", loop_kw);
+                Diagnostic::error("E0206", msg)
+                    .with_primary(*span, String::from("this value has nowhere to go"))
+                    .with_note(String::from("only `loop ... end` has a value"))
+            }
+            AstResolutionErr::UndefinedType { name, used_by } => {
+                Diagnostic::error("E0207", format!("cannot find type `{}`", name))
+                    .with_primary(*used_by, format!("uses the unknown type `{}`", name))
+            }
+            AstResolutionErr::RecursiveType { kw, name } => {
+                let msg = format!("{} `{}` contains itself", kw, name.span);
+                Diagnostic::error("E0208", msg)
+                    .with_primary(name.src_span(), format!("this {} would have no size", kw))
+                    .with_note(format!("{}s are stored by value, so they cannot nest", kw))
+            }
+            AstResolutionErr::NotAStruct { name } => {
+                Diagnostic::error("E0213", format!("`{}` is not a struct", name.span)).with_primary(
+                    name.src_span(),
+                    String::from("cannot be built with `{ ... }`"),
+                )
+            }
+            AstResolutionErr::UnknownField { ty, field } => {
+                let msg = format!("struct `{}` has no field `{}`", ty, field.span);
+                Diagnostic::error("E0209", msg)
+                    .with_primary(field.src_span(), String::from("unknown field"))
+            }
+            AstResolutionErr::MissingFields { ty, fields, span } => {
+                let names: Vec<String> = fields.iter().map(|f| format!("`{}`", f)).collect();
+                let msg = format!("missing fields in `{}` literal", ty);
+                Diagnostic::error("E0210", msg)
+                    .with_primary(*span, format!("{} not given", names.join(", ")))
+            }
+            AstResolutionErr::UndefinedVariant { name } => {
+                let msg = format!("cannot find enum variant `{}`", name.span);
+                Diagnostic::error("E0211", msg)
+                    .with_primary(name.src_span(), String::from("not a variant of any enum"))
+            }
+            AstResolutionErr::PatternArity {
+                variant,
+                expected,
+                found,
+                span,
+            } => {
+                let msg = format!(
+                    "`{}` has {} payload values but the pattern has {}",
+                    variant.span, expected, found
+                );
+                Diagnostic::error("E0212", msg)
+                    .with_primary(*span, format!("expected {} patterns", expected))
+            }
+            AstResolutionErr::CompilerBug(msg) => {
+                Diagnostic::error("E0200", format!("compiler bug: {}", msg))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AstResolutionWarn<'s> {
+    Shadowed { name: TokenData<'s>, prev: SrcSpan },
+    // statements after a `return`, `break` or `continue`
+    Unreachable { stmt: SrcSpan, jump: SrcSpan },
+}
+
+impl<'s> ToDiagnostic for AstResolutionWarn<'s> {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            AstResolutionWarn::Shadowed { name, prev } => {
+                let msg = format!("`{}` shadows an earlier binding", name.span);
+                Diagnostic::warning("W0201", msg)
+                    .with_primary(name.src_span(), String::from("new binding here"))
+                    .with_secondary(*prev, String::from("previous binding hidden in this scope"))
+            }
+            AstResolutionWarn::Unreachable { stmt, jump } => {
+                Diagnostic::warning("W0202", String::from("unreachable statement"))
+                    .with_primary(*stmt, String::from("this is never run"))
+                    .with_secondary(*jump, String::from("any code after this is skipped"))
+            }
+        }
+    }
+}
+
+pub struct NameResolution<'s> {
+    pub sym_table: ModSymTable,
+    pub warnings: Vec<AstResolutionWarn<'s>>,
+}
+
+pub type NameResolutionResult<'s> = Result<NameResolution<'s>, Vec<AstResolutionErr<'s>>>;
 
 impl<'s> ModulePass<'s> for AstNameResolver<'s> {
-    type Output = NameResolutionResult;
+    type Output = NameResolutionResult<'s>;
     fn run_pass(mut self, m: &Module<'s>) -> Self::Output {
         // root scope
         self.scopes.push_scope();
@@ -26,42 +257,89 @@ impl<'s> ModulePass<'s> for AstNameResolver<'s> {
         self.resolve_top_level_contents(&m.body);
 
         // pop root scope
-        self.scopes.pop_scope();
+        self.pop_scope();
 
         if !self.errs.is_empty() {
             Err(self.errs)
         } else {
-            Ok(self.scopes.finish_resolve())
+            Ok(NameResolution {
+                sym_table: self.scopes.finish_resolve(),
+                warnings: self.warns,
+            })
         }
     }
 }
 
 impl<'s> Default for AstNameResolver<'s> {
     fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl<'s> AstNameResolver<'s> {
+    pub fn new(warn_shadowing: bool) -> Self {
         Self {
             scopes: ScopedSymbolStack::default(),
             errs: Vec::new(),
+            warns: Vec::new(),
+            warn_shadowing,
+            loops: Vec::new(),
+            types: HashMap::new(),
+            variants: HashMap::new(),
         }
     }
-}
 
-// TODO error collection and reporting
-impl<'s> AstNameResolver<'s> {
+    fn pop_scope(&mut self) {
+        if let Some(ScopedInsertErr::CompilerBug(msg)) = self.scopes.pop_scope() {
+            self.errs.push(AstResolutionErr::CompilerBug(msg));
+        }
+    }
+
+    fn check_insert(&mut self, name: &TokenData<'s>, res: ScopedInsertResult) {
+        match res {
+            Ok(_) => (),
+            Err(ScopedInsertErr::RedefinedLocal(entry)) => {
+                self.errs.push(AstResolutionErr::Redefinition {
+                    name: *name,
+                    prev: entry.span,
+                });
+            }
+            Err(ScopedInsertErr::CompilerBug(msg)) => {
+                self.errs.push(AstResolutionErr::CompilerBug(msg));
+            }
+        }
+    }
+
+    // closest visible name, for typos
+    fn suggest_similar(&self, name: &str) -> Option<&'s str> {
+        // allow roughly one typo per three characters
+        let max_dist = std::cmp::max(1, name.chars().count() / 3);
+
+        self.scopes
+            .visible_names()
+            .map(|candidate| (edit_distance(name, candidate), candidate))
+            .filter(|(dist, _)| *dist <= max_dist)
+            // ties are broken by name so the output is stable
+            .min()
+            .map(|(_, candidate)| candidate)
+    }
+
     fn resolve_top_level_names(&mut self, items: &[Item<'s>]) {
         for item in items {
             let nid = item.nid;
             match &item.kind {
                 ItemKind::Func(f) => {
-                    if let Err(e) = self.scopes.insert_func(nid, f.proto.name.span) {
-                        self.errs.push(e);
-                    }
+                    let res = self.scopes.insert_func(nid, &f.proto.name);
+                    self.check_insert(&f.proto.name, res);
                 }
                 ItemKind::Extern(proto) => {
                     // add function symbol to global
-                    if let Err(e) = self.scopes.insert_func(nid, proto.name.span) {
-                        self.errs.push(e);
-                    }
+                    let res = self.scopes.insert_func(nid, &proto.name);
+                    self.check_insert(&proto.name, res);
                 }
+                ItemKind::Struct(def) => self.insert_struct(def),
+                ItemKind::Enum(def) => self.insert_enum(def),
+                ItemKind::Error => (),
             }
         }
     }
@@ -70,26 +348,209 @@ impl<'s> AstNameResolver<'s> {
         for item in items {
             match &item.kind {
                 ItemKind::Func(f) => {
+                    self.check_proto_types(&f.proto);
                     self.resolve_func_contents(f);
                 }
-                ItemKind::Extern(_) => (),
+                ItemKind::Extern(proto) => self.check_proto_types(proto),
+                ItemKind::Struct(def) => {
+                    for field in &def.fields {
+                        self.check_type(&field.ty, field.name.src_span());
+                    }
+                    self.check_recursive("struct", &def.name);
+                }
+                ItemKind::Enum(def) => {
+                    for variant in &def.variants {
+                        for ty in &variant.payload {
+                            self.check_type(ty, variant.name.src_span());
+                        }
+                    }
+                    self.check_recursive("enum", &def.name);
+                }
+                ItemKind::Error => (),
+            }
+        }
+    }
+
+    // structs and enums share one namespace
+    fn insert_type(&mut self, info: TypeInfo<'s>) {
+        let name = info.name();
+        match self.types.get(name.span) {
+            Some(prev) => self.errs.push(AstResolutionErr::Redefinition {
+                name,
+                prev: prev.name().src_span(),
+            }),
+            None => {
+                self.types.insert(name.span, info);
+            }
+        }
+    }
+
+    fn insert_enum(&mut self, def: &EnumDef<'s>) {
+        let mut payloads = Vec::new();
+        for variant in &def.variants {
+            // constructors are called like functions
+            let res = self.scopes.insert_func(variant.nid, &variant.name);
+            self.check_insert(&variant.name, res);
+            self.variants
+                .entry(variant.name.span)
+                .or_insert(variant.payload.len());
+            payloads.extend(variant.payload.iter().cloned());
+        }
+
+        self.insert_type(TypeInfo::Enum {
+            name: def.name,
+            payloads,
+        });
+    }
+
+    fn insert_struct(&mut self, def: &StructDef<'s>) {
+        let mut fields: Vec<(TokenData<'s>, Type)> = Vec::with_capacity(def.fields.len());
+        for field in &def.fields {
+            match fields.iter().find(|(prev, _)| prev.span == field.name.span) {
+                Some((prev, _)) => self.errs.push(AstResolutionErr::Redefinition {
+                    name: field.name,
+                    prev: prev.src_span(),
+                }),
+                None => fields.push((field.name, field.ty.clone())),
+            }
+        }
+
+        self.insert_type(TypeInfo::Struct {
+            name: def.name,
+            fields,
+        });
+    }
+
+    fn check_recursive(&mut self, kw: &'static str, name: &TokenData<'s>) {
+        if self.type_contains(name.span, name.span, &mut Vec::new()) {
+            self.errs
+                .push(AstResolutionErr::RecursiveType { kw, name: *name });
+        }
+    }
+
+    // whether a member of `outer`, at any depth, is a `target` by value
+    fn type_contains(&self, outer: &str, target: &str, seen: &mut Vec<String>) -> bool {
+        let info = match self.types.get(outer) {
+            Some(info) => info,
+            None => return false,
+        };
+
+        for ty in info.member_types() {
+            if let TypeKind::Named(name) = ty.kind() {
+                if name == target {
+                    return true;
+                }
+                // other cycles are reported at their own types
+                if !seen.contains(name) {
+                    seen.push(name.clone());
+                    if self.type_contains(name, target, seen) {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn check_proto_types(&mut self, proto: &FuncProto<'s>) {
+        for arg in &proto.args {
+            if let Some(ty) = &arg.ty {
+                self.check_type(ty, arg.name.src_span());
+            }
+        }
+        if let Some(ret) = &proto.ret {
+            self.check_type(ret, proto.name.src_span());
+        }
+    }
+
+    // every struct or enum named in an annotation must exist
+    fn check_type(&mut self, ty: &Type, used_by: SrcSpan) {
+        match ty.kind() {
+            TypeKind::Named(name) if !self.types.contains_key(name.as_str()) => {
+                self.errs.push(AstResolutionErr::UndefinedType {
+                    name: name.clone(),
+                    used_by,
+                });
+            }
+            TypeKind::Func { args, ret, .. } => {
+                for arg in args {
+                    self.check_type(arg, used_by);
+                }
+                self.check_type(ret, used_by);
+            }
+            TypeKind::Array(elem) => self.check_type(elem, used_by),
+            TypeKind::Tuple(elems) => {
+                for elem in elems {
+                    self.check_type(elem, used_by);
+                }
             }
+            _ => (),
         }
     }
 
+    fn resolve_struct_lit(&mut self, name: &TokenData<'s>, inits: &[FieldInit<'s>], span: SrcSpan) {
+        for init in inits {
+            self.resolve_expr(&init.value);
+        }
+
+        let fields = match self.types.get(name.span) {
+            Some(TypeInfo::Struct { fields, .. }) => fields,
+            Some(TypeInfo::Enum { .. }) => {
+                self.errs.push(AstResolutionErr::NotAStruct { name: *name });
+                return;
+            }
+            None => {
+                self.errs.push(AstResolutionErr::UndefinedType {
+                    name: String::from(name.span),
+                    used_by: name.src_span(),
+                });
+                return;
+            }
+        };
+
+        let mut errs = Vec::new();
+        for (idx, init) in inits.iter().enumerate() {
+            if let Some(prev) = inits[..idx].iter().find(|p| p.name.span == init.name.span) {
+                errs.push(AstResolutionErr::Redefinition {
+                    name: init.name,
+                    prev: prev.name.src_span(),
+                });
+            } else if !fields.iter().any(|(f, _)| f.span == init.name.span) {
+                errs.push(AstResolutionErr::UnknownField {
+                    ty: name.span,
+                    field: init.name,
+                });
+            }
+        }
+
+        let missing: Vec<&'s str> = fields
+            .iter()
+            .map(|(f, _)| f.span)
+            .filter(|f| !inits.iter().any(|init| init.name.span == *f))
+            .collect();
+        if !missing.is_empty() {
+            errs.push(AstResolutionErr::MissingFields {
+                ty: name.span,
+                fields: missing,
+                span,
+            });
+        }
+
+        self.errs.extend(errs);
+    }
+
     fn resolve_func_contents(&mut self, func: &Function<'s>) {
         // extra scope required just for the function args
         self.scopes.push_scope();
 
         for arg in &func.proto.args {
-            if let Err(e) = self.scopes.insert_local(arg.nid, arg.name.span) {
-                self.errs.push(e);
-            }
+            let res = self.scopes.insert_local(arg.nid, &arg.name);
+            self.check_insert(&arg.name, res);
         }
 
         self.resolve_expr(&func.body);
 
-        self.scopes.pop_scope();
+        self.pop_scope();
     }
     fn resolve_expr(&mut self, expr: &Expr<'s>) {
         match &*expr.kind {
@@ -98,13 +559,52 @@ impl<'s> AstNameResolver<'s> {
                 for sub in b {
                     self.resolve_expr(sub)
                 }
-                self.scopes.pop_scope();
+                self.pop_scope();
+
+                // one warning for the first dead statement is enough
+                let jump = b.iter().position(|sub| {
+                    matches!(
+                        &*sub.kind,
+                        ExprKind::Return(_) | ExprKind::Break(_) | ExprKind::Continue
+                    )
+                });
+                if let Some(idx) = jump {
+                    if let Some(dead) = b.get(idx + 1) {
+                        self.warns.push(AstResolutionWarn::Unreachable {
+                            stmt: dead.span,
+                            jump: b[idx].span,
+                        });
+                    }
+                }
             }
             ExprKind::Decl(vd) => {
                 self.resolve_expr(&vd.value);
-                if let Err(e) = self.scopes.insert_local(expr.nid, vd.bound.span) {
-                    self.errs.push(e);
+                if let Some(ty) = &vd.ty {
+                    self.check_type(ty, vd.bound.src_span());
+                }
+
+                // a binding in an inner scope gets a fresh symbol
+                // and hides the outer one until the scope ends
+                let outer = self.scopes.lookup_entry(vd.bound.span).map(|e| e.span);
+                let res = self.scopes.insert_local(expr.nid, &vd.bound);
+
+                match (outer, &res) {
+                    (Some(prev), Ok(_)) if self.warn_shadowing => {
+                        self.warns.push(AstResolutionWarn::Shadowed {
+                            name: vd.bound,
+                            prev,
+                        });
+                    }
+                    _ => self.check_insert(&vd.bound, res),
+                }
+            }
+            // the names are visible after the declaration, like `val`
+            ExprKind::Destructure { pat, ty, value, .. } => {
+                self.resolve_expr(value);
+                if let Some(ty) = ty {
+                    self.check_type(ty, pat.span);
                 }
+                self.resolve_pattern(pat);
             }
             ExprKind::Let { bound, let_body } => {
                 // TODO WANT: use before definition should be acceptable in this block
@@ -114,26 +614,64 @@ impl<'s> AstNameResolver<'s> {
                     self.resolve_expr(decl);
                 }
                 self.resolve_expr(let_body);
-                self.scopes.pop_scope();
+                self.pop_scope();
             }
             ExprKind::Var(td) => match self.scopes.lookup(td.span) {
                 Some(sid_ref) => {
                     let sid = *sid_ref;
-                    self.scopes.insert_local_reuse(expr.nid, sid);
+                    if let Some(ScopedInsertErr::CompilerBug(msg)) =
+                        self.scopes.insert_local_reuse(expr.nid, sid)
+                    {
+                        self.errs.push(AstResolutionErr::CompilerBug(msg));
+                    }
+                }
+                None => {
+                    let suggestion = self.suggest_similar(td.span);
+                    self.errs.push(AstResolutionErr::Undefined {
+                        name: td.span,
+                        loc: td.src_span(),
+                        suggestion,
+                    });
                 }
-                None => unimplemented!("Unimplemented use before def"),
             },
             ExprKind::Call { callee, args } => {
+                // a builtin is only called, it has no symbol
+                let builtin = match &*callee.kind {
+                    ExprKind::Var(td) => {
+                        self.scopes.lookup(td.span).is_none()
+                            && Builtin::from_name(td.span).is_some()
+                    }
+                    _ => false,
+                };
+                if !builtin {
+                    self.resolve_expr(callee);
+                }
+
                 for arg in args {
                     self.resolve_expr(arg)
                 }
-
-                self.resolve_expr(callee)
+            }
+            ExprKind::StructLit { name, fields } => {
+                self.resolve_struct_lit(name, fields, expr.span)
+            }
+            ExprKind::ArrayLit(elems) | ExprKind::TupleLit(elems) => {
+                for elem in elems {
+                    self.resolve_expr(elem);
+                }
+            }
+            ExprKind::Index { base, index } => {
+                self.resolve_expr(base);
+                self.resolve_expr(index);
+            }
+            // fields are looked up once the type of `base` is known
+            ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => {
+                self.resolve_expr(base)
             }
             ExprKind::Binary { lhs, rhs, .. } => {
                 self.resolve_expr(lhs);
                 self.resolve_expr(rhs);
             }
+            ExprKind::Unary { operand, .. } => self.resolve_expr(operand),
             ExprKind::If {
                 cond,
                 if_body,
@@ -145,9 +683,313 @@ impl<'s> AstNameResolver<'s> {
             }
             ExprKind::While { cond, while_body } => {
                 self.resolve_expr(cond);
-                self.resolve_expr(while_body);
+                self.resolve_loop_body("while", while_body);
+            }
+            ExprKind::Loop(loop_body) => self.resolve_loop_body("loop", loop_body),
+            ExprKind::For { range, for_body } => {
+                self.resolve_expr(&range.start);
+                self.resolve_expr(&range.end);
+                if let Some(step) = &range.step {
+                    self.resolve_expr(step);
+                }
+
+                // the loop variable is only visible in the body
+                self.scopes.push_scope();
+                let res = self.scopes.insert_local(range.var_nid, &range.var);
+                self.check_insert(&range.var, res);
+                self.resolve_loop_body("for", for_body);
+                self.pop_scope();
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.resolve_expr(scrutinee);
+                for arm in arms {
+                    // a bound name is only visible in its arm
+                    self.scopes.push_scope();
+                    self.resolve_pattern(&arm.pat);
+                    self.resolve_expr(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            ExprKind::Return(value) => self.resolve_expr(value),
+            ExprKind::Break(value) => {
+                match (self.loops.last(), value) {
+                    (None, _) => self.errs.push(AstResolutionErr::OutsideLoop {
+                        kw: "break",
+                        span: expr.span,
+                    }),
+                    (Some(&loop_kw), Some(value)) if loop_kw != "loop" => {
+                        self.errs.push(AstResolutionErr::BreakWithValue {
+                            loop_kw,
+                            span: value.span,
+                        })
+                    }
+                    _ => (),
+                }
+                if let Some(value) = value {
+                    self.resolve_expr(value);
+                }
+            }
+            ExprKind::Continue if self.loops.is_empty() => {
+                self.errs.push(AstResolutionErr::OutsideLoop {
+                    kw: "continue",
+                    span: expr.span,
+                });
+            }
+            ExprKind::Lit(_) | ExprKind::Continue | ExprKind::Error => (),
+        }
+    }
+
+    // a name is a variant when one is declared with it, otherwise a binding
+    fn resolve_pattern(&mut self, pat: &Pattern<'s>) {
+        match &pat.kind {
+            PatternKind::Bind(name) => match self.variants.get(name.span) {
+                Some(0) => (),
+                Some(&expected) => self.errs.push(AstResolutionErr::PatternArity {
+                    variant: *name,
+                    expected,
+                    found: 0,
+                    span: pat.span,
+                }),
+                None => {
+                    let res = self.scopes.insert_local(pat.nid, name);
+                    self.check_insert(name, res);
+                }
+            },
+            PatternKind::Variant { name, args } => {
+                match self.variants.get(name.span) {
+                    None => self
+                        .errs
+                        .push(AstResolutionErr::UndefinedVariant { name: *name }),
+                    Some(&expected) if expected != args.len() => {
+                        self.errs.push(AstResolutionErr::PatternArity {
+                            variant: *name,
+                            expected,
+                            found: args.len(),
+                            span: pat.span,
+                        })
+                    }
+                    Some(_) => (),
+                }
+                for arg in args {
+                    self.resolve_pattern(arg);
+                }
+            }
+            PatternKind::Tuple(elems) => {
+                for elem in elems {
+                    self.resolve_pattern(elem);
+                }
+            }
+            PatternKind::Wildcard | PatternKind::Lit(_) => (),
+        }
+    }
+
+    fn resolve_loop_body(&mut self, loop_kw: &'static str, body: &Expr<'s>) {
+        self.loops.push(loop_kw);
+        self.resolve_expr(body);
+        self.loops.pop();
+    }
+}
+
+// Edit distance between two names, where swapping two
+// neighbouring characters counts as a single edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // dist[i][j] is the distance between a[..i] and b[..j]
+    let mut dist = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut d = (dist[i - 1][j] + 1)
+                .min(dist[i][j - 1] + 1)
+                .min(dist[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(dist[i - 2][j - 2] + 1);
+            }
+            dist[i][j] = d;
+        }
+    }
+
+    dist[a.len()][b.len()]
+}
+
+#[test]
+fn test_undefined_name_suggests_closest() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nextern fun sin(x)\nfun foo(value)\n  sine(valeu)\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 2);
+    assert!(diags.iter().all(|d| d.code == "E0202"));
+    assert!(diags
+        .iter()
+        .any(|d| d.notes == vec![String::from("did you mean `sin`?")]));
+    assert!(diags
+        .iter()
+        .any(|d| d.notes == vec![String::from("did you mean `value`?")]));
+}
+
+// symbols of every `val` named `name` in a function body, outermost first
+#[cfg(test)]
+fn decl_syms(
+    checked: &crate::cli::check::CheckedModule,
+    name: &str,
+) -> Vec<crate::ast::symbol::SymId> {
+    use crate::ast::symbol::SymId;
+
+    fn walk(e: &Expr, name: &str, mst: &ModSymTable, out: &mut Vec<SymId>) {
+        match &*e.kind {
+            ExprKind::Decl(vd) => {
+                if vd.bound.span == name {
+                    out.push(*mst.lookup(&e.nid).unwrap());
+                }
+                walk(&vd.value, name, mst, out);
             }
-            ExprKind::Lit(_) => (),
+            ExprKind::Block(block) => block.iter().for_each(|sub| walk(sub, name, mst, out)),
+            ExprKind::Let { bound, let_body } => {
+                bound.iter().for_each(|decl| walk(decl, name, mst, out));
+                walk(let_body, name, mst, out);
+            }
+            ExprKind::While { while_body, .. } => walk(while_body, name, mst, out),
+            _ => (),
+        }
+    }
+
+    let mut out = Vec::new();
+    for item in &checked.module.body {
+        if let ItemKind::Func(f) = &item.kind {
+            walk(&f.body, name, &checked.sym_table, &mut out);
         }
     }
+    out
+}
+
+#[test]
+fn test_inner_scopes_shadow_until_they_end() {
+    use crate::cli::check::expect_checked;
+
+    // each `x` has its own type, the last line only checks if the outer one is back
+    let src = "mod m
+fun f(): bool
+    val x = true
+    val a = do
+        val x = 1.5
+        x * 2.0
+    end
+    val b = let
+        x = 2
+    in
+        x + 1
+    end
+    val mut i = 0
+    while i < b
+        val x = 'c'
+        i = i + 1
+    end
+    x
+end
+";
+    let checked = expect_checked(src);
+    assert!(checked.warnings.is_empty());
+
+    let syms = decl_syms(&checked, "x");
+    assert_eq!(syms.len(), 4);
+    for (idx, sym) in syms.iter().enumerate() {
+        assert!(!syms[idx + 1..].contains(sym));
+    }
+}
+
+#[test]
+fn test_redefined_in_same_scope() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun f()\n    val x = 1\n    val x = 2\n    x\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0201");
+    assert_eq!(diags[0].msg, "`x` is defined more than once");
+}
+
+#[test]
+fn test_shadowing_warns_only_when_asked() {
+    use crate::cli::check::{expect_checked, expect_checked_with, CheckOptions};
+
+    let src = "mod m\nfun f()\n    val x = 1\n    do\n        val x = 2\n        x\n    end\nend\n";
+    assert!(expect_checked(src).warnings.is_empty());
+
+    let opts = CheckOptions {
+        warn_shadowing: true,
+    };
+    let checked = expect_checked_with(src, opts);
+    assert_eq!(checked.warnings.len(), 1);
+    assert_eq!(checked.warnings[0].code, "W0201");
+    assert_eq!(checked.warnings[0].labels[1].span.start.line, 3);
+}
+
+#[test]
+fn test_break_outside_loop() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun f(n)\n    while n < 3\n        break\n    end\n    continue\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0205");
+    assert_eq!(diags[0].msg, "`continue` outside of a loop");
+}
+
+#[test]
+fn test_break_value_outside_loop_expr() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun f(n)\n    while n < 3\n        break n\n    end\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0206");
+}
+
+#[test]
+fn test_code_after_return_warns() {
+    use crate::cli::check::expect_checked;
+
+    let src = "mod m\nfun f(n)\n    if n < 0\n        return 0\n        n + 1\n    else\n        n\n    end\nend\n";
+    let checked = expect_checked(src);
+
+    assert_eq!(checked.warnings.len(), 1);
+    assert_eq!(checked.warnings[0].code, "W0202");
+}
+
+#[test]
+fn test_struct_literal_fields() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nstruct P x: f64, y: f64 end\nfun f()\n    P { x: 1.0, z: 2.0 }\nend\n";
+    let diags = expect_errors(src);
+
+    let codes: Vec<_> = diags.iter().map(|d| d.code).collect();
+    assert_eq!(codes, vec!["E0209", "E0210"]);
+    assert_eq!(diags[0].msg, "struct `P` has no field `z`");
+}
+
+#[test]
+fn test_variant_pattern_arity() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nenum S A(f64) | B(f64, f64) end\nfun f(s: S): f64\n    match s\n    case A(x, y) => x\n    case B => 0.0\n    end\nend\n";
+    let diags = expect_errors(src);
+
+    let codes: Vec<_> = diags.iter().map(|d| d.code).collect();
+    assert_eq!(codes, vec!["E0212", "E0212"]);
 }
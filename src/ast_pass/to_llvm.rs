@@ -1,26 +1,127 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
 use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::{Linkage, Module};
 use inkwell::passes::{PassManager, PassManagerBuilder};
-use inkwell::types::FunctionType;
-use inkwell::values::{BasicValue, BasicValueEnum, FloatValue, FunctionValue, PointerValue};
-use inkwell::{FloatPredicate, OptimizationLevel};
+use inkwell::types::{BasicType, BasicTypeEnum, FunctionType, StructType};
+use inkwell::values::{
+    BasicValue, BasicValueEnum, CallableValue, FloatValue, FunctionValue, IntValue, PointerValue,
+    StructValue,
+};
+use inkwell::{AddressSpace, FloatPredicate, IntPredicate, OptimizationLevel};
 
 // use crate::codegen::CodeGenerator;
 use crate::ast;
-use crate::ast::ops::BinaryOp;
+use crate::ast::ops::{BinaryOp, UnaryOp};
 use crate::ast::symbol::{ModSymTable, SymId};
+use crate::ast::token::SrcSpan;
 use crate::ast::{
+    Builtin,
+    EnumTable,
     ExprKind,
+    FloatWidth,
     ItemKind,
+    Lit,
     NodeId, // Place
+    PatternKind,
+    Type,
+    TypeKind,
+    TypeTable,
+    VariantInfo,
 };
+use crate::diag::{Diagnostic, ToDiagnostic};
 
+#[derive(Debug)]
 pub enum CompileError {
-    TypeErr,
+    // name resolution did not map this node to a symbol
+    UnresolvedNode(SrcSpan),
+    UnknownFunction { name: String, span: SrcSpan },
+    UnknownVariable { name: String, span: SrcSpan },
+    // type checking let a callee through that is not a function pointer
+    IndirectCall(SrcSpan),
+    // constructors have no function to point to
+    VariantAsValue { name: String, span: SrcSpan },
+    InvalidAssignTarget(SrcSpan),
+    AssignToConst { name: String, span: SrcSpan },
+    UnsupportedOp { op: BinaryOp, span: SrcSpan },
+    // LLVM rejected the function we generated
+    InvalidFunction { name: String, span: SrcSpan },
+    // type checking did not give this node a type
+    UntypedNode(SrcSpan),
+    // name resolution lets `break` and `continue` through only in loops
+    JumpOutsideLoop(SrcSpan),
+    // type checking did not find this field on the struct
+    UnknownField { name: String, span: SrcSpan },
+}
+
+impl ToDiagnostic for CompileError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            CompileError::UnresolvedNode(span) => Diagnostic::error(
+                "E0901",
+                String::from("compiler bug: expression was not name resolved"),
+            )
+            .with_primary(*span, String::from("no symbol for this expression")),
+            CompileError::UnknownFunction { name, span } => {
+                Diagnostic::error("E0902", format!("call to unknown function `{}`", name))
+                    .with_primary(*span, String::from("not a function in this module"))
+            }
+            CompileError::UnknownVariable { name, span } => {
+                Diagnostic::error("E0903", format!("use of unknown variable `{}`", name))
+                    .with_primary(*span, String::from("not declared in this function"))
+            }
+            CompileError::IndirectCall(span) => Diagnostic::error(
+                "E0904",
+                String::from("compiler bug: callee is not a function pointer"),
+            )
+            .with_primary(*span, String::from("cannot be called")),
+            CompileError::InvalidAssignTarget(span) => Diagnostic::error(
+                "E0905",
+                String::from("invalid left hand side of assignment"),
+            )
+            .with_primary(*span, String::from("cannot assign to this expression")),
+            CompileError::AssignToConst { name, span } => {
+                Diagnostic::error("E0906", format!("cannot assign twice to `{}`", name))
+                    .with_primary(*span, String::from("not declared with `val mut`"))
+            }
+            CompileError::UnsupportedOp { op, span } => {
+                Diagnostic::error("E0907", format!("operator {:?} is not supported yet", op))
+                    .with_primary(*span, String::from("in this expression"))
+            }
+            CompileError::InvalidFunction { name, span } => Diagnostic::error(
+                "E0908",
+                format!("compiler bug: generated invalid LLVM for `{}`", name),
+            )
+            .with_primary(*span, String::from("in this function")),
+            CompileError::UntypedNode(span) => Diagnostic::error(
+                "E0909",
+                String::from("compiler bug: expression was not type checked"),
+            )
+            .with_primary(*span, String::from("no type for this expression")),
+            CompileError::JumpOutsideLoop(span) => Diagnostic::error(
+                "E0910",
+                String::from("compiler bug: jump outside of a loop"),
+            )
+            .with_primary(*span, String::from("no loop to jump in")),
+            CompileError::UnknownField { name, span } => Diagnostic::error(
+                "E0911",
+                format!("compiler bug: field `{}` was not type checked", name),
+            )
+            .with_primary(*span, String::from("no such field")),
+            CompileError::VariantAsValue { name, span } => Diagnostic::error(
+                "E0912",
+                format!("variant `{}` cannot be used as a function value yet", name),
+            )
+            .with_primary(*span, String::from("only calls can build this variant"))
+            .with_note(format!(
+                "wrap it in a function that calls `{}` with its arguments",
+                name
+            )),
+        }
+    }
 }
 
 pub type BuildResult<T> = Result<T, CompileError>;
@@ -28,6 +129,8 @@ pub type BuildResult<T> = Result<T, CompileError>;
 pub struct LlvmBackend {
     pub context: Context,
     opt: OptimizationLevel,
+    // compare every array index against the length
+    bounds_checks: bool,
 }
 
 impl LlvmBackend {
@@ -39,8 +142,14 @@ impl LlvmBackend {
         Self {
             context: Context::create(),
             opt,
+            bounds_checks: true,
         }
     }
+
+    pub fn without_bounds_checks(mut self) -> Self {
+        self.bounds_checks = false;
+        self
+    }
 }
 
 pub enum NameMangleOptions {
@@ -77,7 +186,27 @@ fn mangle_into_pretty_name(p: &ast::token::TokenData) -> String {
 // }
 
 impl<'s> LlvmBackend {
-    pub fn compile_mod(&'s self, module: ast::Module<'s>, mst: &'s ModSymTable) -> Module<'s> {
+    pub fn compile_mod(
+        &'s self,
+        module: ast::Module<'s>,
+        mst: &'s ModSymTable,
+        types: &'s TypeTable,
+    ) -> Result<Module<'s>, Vec<CompileError>> {
+        let (llvm_module, errs) = self.build_mod(module, mst, types);
+        if errs.is_empty() {
+            Ok(llvm_module)
+        } else {
+            Err(errs)
+        }
+    }
+
+    // the module is built as far as it goes, even when functions failed
+    fn build_mod(
+        &'s self,
+        module: ast::Module<'s>,
+        mst: &'s ModSymTable,
+        types: &'s TypeTable,
+    ) -> (Module<'s>, Vec<CompileError>) {
         // let mod_name = mangle_mod_name(&module.decl.name, NameMangleOptions::Pretty);
         let mod_name = mangle_mod_name(&module.decl, NameMangleOptions::Pretty);
         let llvm_module = self.context.create_module(mod_name.as_str());
@@ -87,24 +216,47 @@ impl<'s> LlvmBackend {
         let fpm = PassManager::create(&llvm_module);
         pass_manager_builder.populate_function_pass_manager(&fpm);
 
-        let mut art = BuildState::new(&self.context, llvm_module, fpm, mst);
+        let mut art = BuildState::new(&self.context, llvm_module, fpm, mst, types);
+        art.enums = EnumTable::new(&module);
+        art.bounds_checks = self.bounds_checks;
+
+        // fields and payloads may name types defined later
+        art.declare_types(&module.body);
 
-        for item in module.body {
-            art.build_item(&item)
+        // declare everything first so functions can call ones defined later
+        for item in &module.body {
+            art.declare_item(item)
         }
 
-        art.module
+        for item in &module.body {
+            art.build_item(item)
+        }
+
+        (art.module, art.errs)
     }
 }
 
+// an LLVM struct and its fields in order
+struct StructLayout<'c> {
+    ty: StructType<'c>,
+    fields: Vec<(&'c str, Type)>,
+}
+
 pub struct BuildState<'c> {
     context: &'c Context,
     module: Module<'c>,
     mod_syms: &'c ModSymTable,
+    types: &'c TypeTable,
+    structs: HashMap<&'c str, StructLayout<'c>>,
+    enums: EnumTable,
+    // a tag, then room for the largest payload
+    enum_types: HashMap<&'c str, StructType<'c>>,
+    bounds_checks: bool,
     builder: Builder<'c>,
     // func_sym: HashMap<&'c str, FunctionValue<'c>>
     // var_sym: HashMap<&'c str, PointerValue<'c>>,
     fpm: PassManager<FunctionValue<'c>>,
+    errs: Vec<CompileError>,
 }
 
 impl<'a, 'c> BuildState<'c> {
@@ -113,76 +265,304 @@ impl<'a, 'c> BuildState<'c> {
         module: Module<'c>,
         fpm: PassManager<FunctionValue<'c>>,
         mst: &'c ModSymTable,
+        types: &'c TypeTable,
     ) -> Self {
         Self {
             context,
             module,
             mod_syms: mst,
+            types,
+            structs: HashMap::new(),
+            enums: EnumTable::default(),
+            enum_types: HashMap::new(),
+            bounds_checks: true,
             builder: context.create_builder(),
             // func_sym: HashMap::new(),
             // var_sym: HashMap::new(),
             fpm,
+            errs: Vec::new(),
+        }
+    }
+
+    fn declare_types(&mut self, items: &[ast::Item<'c>]) {
+        for item in items {
+            match &item.kind {
+                ItemKind::Struct(def) => {
+                    let layout = StructLayout {
+                        ty: self.context.opaque_struct_type(def.name.span),
+                        fields: def
+                            .fields
+                            .iter()
+                            .map(|field| (field.name.span, field.ty.clone()))
+                            .collect(),
+                    };
+                    self.structs.insert(def.name.span, layout);
+                }
+                ItemKind::Enum(def) => {
+                    let ty = self.context.opaque_struct_type(def.name.span);
+                    self.enum_types.insert(def.name.span, ty);
+                }
+                _ => (),
+            }
+        }
+
+        // every name is known now, fields are laid out in declaration order
+        for item in items {
+            match &item.kind {
+                ItemKind::Struct(def) => {
+                    let field_types: Vec<_> = def
+                        .fields
+                        .iter()
+                        .map(|field| self.llvm_type(&field.ty))
+                        .collect();
+                    self.structs[def.name.span]
+                        .ty
+                        .set_body(field_types.as_slice(), false);
+                }
+                // payloads are written through a pointer to their own struct
+                ItemKind::Enum(def) => {
+                    let words = def
+                        .variants
+                        .iter()
+                        .map(|v| v.payload.iter().map(|ty| self.size_in_words(ty)).sum())
+                        .max()
+                        .unwrap_or(0);
+                    let mut body = vec![self.context.i32_type().into()];
+                    if words > 0 {
+                        body.push(self.context.i64_type().array_type(words).into());
+                    }
+                    self.enum_types[def.name.span].set_body(body.as_slice(), false);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // room a value needs in 8 byte words, every scalar fits in one
+    fn size_in_words(&self, ty: &Type) -> u32 {
+        match ty.kind() {
+            TypeKind::Nil => 0,
+            TypeKind::Array(_) => 2,
+            TypeKind::Tuple(elems) => elems.iter().map(|ty| self.size_in_words(ty)).sum(),
+            TypeKind::Named(name) => match self.enums.variants(name) {
+                Some(variants) => {
+                    let payload = variants
+                        .iter()
+                        .map(|v| v.payload.iter().map(|ty| self.size_in_words(ty)).sum());
+                    1 + payload.max().unwrap_or(0)
+                }
+                None => self.structs[name.as_str()]
+                    .fields
+                    .iter()
+                    .map(|(_, ty)| self.size_in_words(ty))
+                    .sum(),
+            },
+            _ => 1,
+        }
+    }
+
+    // the payload of one variant as a struct
+    fn variant_type(&self, variant: &VariantInfo) -> StructType<'c> {
+        let field_types: Vec<_> = variant
+            .payload
+            .iter()
+            .map(|ty| self.llvm_type(ty))
+            .collect();
+        self.context.struct_type(field_types.as_slice(), false)
+    }
+
+    // position of `field` in the struct `ty`
+    fn field_index(&self, ty: &Type, field: &ast::token::TokenData) -> BuildResult<u32> {
+        let layout = match ty.kind() {
+            TypeKind::Named(name) => self.structs.get(name.as_str()),
+            _ => None,
+        };
+        let idx =
+            layout.and_then(|layout| layout.fields.iter().position(|(f, _)| *f == field.span));
+        match idx {
+            Some(idx) => Ok(idx as u32),
+            None => Err(CompileError::UnknownField {
+                name: String::from(field.span),
+                span: field.src_span(),
+            }),
+        }
+    }
+
+    // C functions the generated code calls, declared on first use
+    fn runtime_func(&self, name: &str, ty: FunctionType<'c>) -> FunctionValue<'c> {
+        match self.module.get_function(name) {
+            Some(func) => func,
+            None => self.module.add_function(name, ty, None),
+        }
+    }
+
+    fn declare_item(&mut self, it: &ast::Item<'c>) {
+        let declared = match &it.kind {
+            ItemKind::Func(f) => self.build_func_decl(&f.proto, it.nid, None),
+            ItemKind::Extern(fp) => self.build_extern(fp, it.nid),
+            ItemKind::Struct(_) | ItemKind::Enum(_) | ItemKind::Error => return,
+        };
+
+        if let Err(e) = declared {
+            self.errs.push(e);
         }
     }
 
     fn build_item(&mut self, it: &ast::Item<'c>) {
         match &it.kind {
             ItemKind::Func(f) => self.build_function(f),
-            ItemKind::Extern(fp) => self.build_extern(fp), // ItemKind::Import(_) => (),
+            // ItemKind::Import(_) => (),
+            ItemKind::Extern(_) | ItemKind::Struct(_) | ItemKind::Enum(_) | ItemKind::Error => (),
         }
     }
 
     fn build_function(&mut self, f: &'a ast::Function<'c>) {
-        let func = self.build_func_decl(&f.proto, None);
+        let name = f.proto.name;
+        let func = match self.module.get_function(name.span) {
+            Some(func) => func,
+            // declaration failed and was already reported
+            None => return,
+        };
 
-        FuncBuild::new(self, func, f).build();
+        let built = FuncBuild::new(self, func, f).build();
 
-        if func.verify(true) {
-            self.fpm.run_on(&func);
-        } else {
-            // unsafe {
-            //     function.delete();
-            // }
-            self.module.print_to_stderr();
-            panic!("Invalid generated function.")
-        }
+        let failed = match built {
+            Ok(_) if func.verify(true) => {
+                self.fpm.run_on(&func);
+                return;
+            }
+            Ok(_) => CompileError::InvalidFunction {
+                name: String::from(name.span),
+                span: name.src_span(),
+            },
+            Err(e) => e,
+        };
+
+        // callers built earlier may already call this function, so it is kept
+        // and the whole module is thrown away once any function failed
+        self.errs.push(failed);
     }
 
-    fn build_extern(&mut self, fp: &ast::FuncProto<'c>) {
-        self.build_func_decl(fp, None); // Some(Linkage::ExternalWeak)
+    fn build_extern(
+        &mut self,
+        fp: &ast::FuncProto<'c>,
+        nid: NodeId,
+    ) -> BuildResult<FunctionValue<'c>> {
+        self.build_func_decl(fp, nid, None) // Some(Linkage::ExternalWeak)
     }
 
     fn build_func_decl(
         &mut self,
         fp: &ast::FuncProto<'c>,
+        nid: NodeId,
         link: Option<Linkage>,
-    ) -> FunctionValue<'c> {
-        let ty = self.build_func_type(fp);
+    ) -> BuildResult<FunctionValue<'c>> {
+        let ty = match self.types.lookup(&nid).map(|ty| ty.kind()) {
+            Some(TypeKind::Func {
+                args,
+                ret,
+                variadic,
+            }) => self.llvm_func_type(args, ret, *variadic),
+            _ => return Err(CompileError::UntypedNode(fp.name.src_span())),
+        };
 
         let func_val = self.module.add_function(fp.name.span, ty, link);
 
         for (i, arg) in func_val.get_param_iter().enumerate() {
-            arg.into_float_value().set_name(fp.args[i].name.span);
+            arg.set_name(fp.args[i].name.span);
         }
 
-        func_val
+        Ok(func_val)
     }
 
-    fn build_func_type(&mut self, fp: &ast::FuncProto<'c>) -> FunctionType<'c> {
-        let f64_type = self.context.f64_type();
-
-        let arg_types: Vec<_> = fp.args.iter().map(|_| f64_type.into()).collect();
+    fn llvm_func_type(&self, args: &[Type], ret: &Type, variadic: bool) -> FunctionType<'c> {
+        let arg_types: Vec<_> = args.iter().map(|arg| self.llvm_type(arg)).collect();
+        match ret.kind() {
+            // C can call and define these as void functions
+            TypeKind::Nil => self
+                .context
+                .void_type()
+                .fn_type(arg_types.as_slice(), variadic),
+            _ => self.llvm_type(ret).fn_type(arg_types.as_slice(), variadic),
+        }
+    }
 
-        f64_type.fn_type(arg_types.as_slice(), false)
+    fn llvm_type(&self, ty: &Type) -> BasicTypeEnum<'c> {
+        match ty.kind() {
+            TypeKind::Int(w) | TypeKind::UInt(w) => {
+                self.context.custom_width_int_type(w.bits()).into()
+            }
+            TypeKind::Float(FloatWidth::Float32) => self.context.f32_type().into(),
+            TypeKind::Float(FloatWidth::Float64) => self.context.f64_type().into(),
+            TypeKind::Bool => self.context.bool_type().into(),
+            // a unicode scalar value
+            TypeKind::Char => self.context.i32_type().into(),
+            // an empty struct, nil has no data
+            TypeKind::Nil => self.context.struct_type(&[], false).into(),
+            // i8* like C
+            TypeKind::Str => self
+                .context
+                .i8_type()
+                .ptr_type(AddressSpace::Generic)
+                .into(),
+            TypeKind::Func {
+                args,
+                ret,
+                variadic,
+            } => self
+                .llvm_func_type(args, ret, *variadic)
+                .ptr_type(AddressSpace::Generic)
+                .into(),
+            // passed and returned by value
+            TypeKind::Named(name) => match self.structs.get(name.as_str()) {
+                Some(layout) => layout.ty.into(),
+                None => self.enum_types[name.as_str()].into(),
+            },
+            // a pointer to the first element and the length
+            TypeKind::Array(elem) => {
+                let data = self.llvm_type(elem).ptr_type(AddressSpace::Generic);
+                let len = self.context.i64_type();
+                self.context
+                    .struct_type(&[data.into(), len.into()], false)
+                    .into()
+            }
+            // an anonymous struct, returned like any other
+            TypeKind::Tuple(elems) => {
+                let elem_types: Vec<_> = elems.iter().map(|ty| self.llvm_type(ty)).collect();
+                self.context
+                    .struct_type(elem_types.as_slice(), false)
+                    .into()
+            }
+            TypeKind::Var(_) => unreachable!("type variables are resolved by type checking"),
+        }
     }
 }
 
+// where a variable lives
+#[derive(Clone, Copy)]
+enum VarSlot<'c> {
+    // args and `val` bindings are SSA values
+    Value(BasicValueEnum<'c>),
+    // `val mut` bindings are stored on the stack
+    Stack(PointerValue<'c>),
+}
+
+// where `continue` and `break` jump to
+struct LoopTargets<'c> {
+    next: BasicBlock<'c>,
+    exit: BasicBlock<'c>,
+    // values given to `break` in a `loop` and the blocks they come from
+    breaks: Option<Vec<(BasicValueEnum<'c>, BasicBlock<'c>)>>,
+}
+
 struct FuncBuild<'a, 'c> {
     state: &'a BuildState<'c>,
     func: FunctionValue<'c>,
     entry: BasicBlock<'c>,
     ast_func: &'a ast::Function<'c>,
-    var_sym: HashMap<SymId, BasicValueEnum<'c>>,
+    var_sym: HashMap<SymId, VarSlot<'c>>,
+    // innermost loop last
+    loops: Vec<LoopTargets<'c>>,
 }
 
 impl<'a, 'c> FuncBuild<'a, 'c> {
@@ -198,6 +578,7 @@ impl<'a, 'c> FuncBuild<'a, 'c> {
             entry,
             ast_func: ast_f,
             var_sym: HashMap::new(),
+            loops: Vec::new(),
         }
     }
 
@@ -211,7 +592,34 @@ impl<'a, 'c> FuncBuild<'a, 'c> {
         &self.state.module
     }
 
-    fn build(mut self) -> FunctionValue<'c> {
+    fn lookup_sym(&self, nid: &NodeId, span: SrcSpan) -> BuildResult<SymId> {
+        match self.state.mod_syms.lookup(nid) {
+            Some(s) => Ok(*s),
+            None => Err(CompileError::UnresolvedNode(span)),
+        }
+    }
+
+    fn expr_type(&self, expr: &ast::Expr<'c>) -> BuildResult<Type> {
+        match self.state.types.lookup(&expr.nid) {
+            Some(ty) => Ok(ty.clone()),
+            None => Err(CompileError::UntypedNode(expr.span)),
+        }
+    }
+
+    // the value of statements
+    fn nil_value(&self) -> BasicValueEnum<'c> {
+        self.context().const_struct(&[], false).into()
+    }
+
+    // functions returning nil are void
+    fn build_return(&self, val: BasicValueEnum<'c>) {
+        match self.func.get_type().get_return_type() {
+            Some(_) => self.builder().build_return(Some(&val)),
+            None => self.builder().build_return(None),
+        };
+    }
+
+    fn build(mut self) -> BuildResult<FunctionValue<'c>> {
         self.builder().position_at_end(self.entry);
 
         // const arg only supported
@@ -219,28 +627,26 @@ impl<'a, 'c> FuncBuild<'a, 'c> {
         for (i, arg_val) in self.func.get_param_iter().enumerate() {
             let arg = &self.ast_func.proto.args[i];
 
-            let sid = match self.state.mod_syms.lookup(&arg.nid) {
-                Some(s) => *s,
-                None => unimplemented!("NodeId => SymId lookup failed"),
-            };
+            let sid = self.lookup_sym(&arg.nid, arg.name.src_span())?;
 
             // let alloca = self.create_entry_block_alloca(arg_name);
             // self.builder().build_store(alloca, arg);
 
             self.var_sym.insert(
-                sid, arg_val, // BasicValueEnum::PointerValue(alloca),
+                sid,
+                VarSlot::Value(arg_val), // VarSlot::Stack(alloca),
             );
         }
 
         // compile body
-        let body = self.build_expr(&self.ast_func.body);
+        let body = self.build_expr(&self.ast_func.body)?;
 
-        self.builder().build_return(Some(&body));
+        self.build_return(body);
 
-        self.func
+        Ok(self.func)
     }
 
-    fn create_entry_block_alloca(&mut self, name: &str) -> PointerValue<'c> {
+    fn create_entry_block_alloca(&mut self, name: &str, ty: BasicTypeEnum<'c>) -> PointerValue<'c> {
         // not sure why this is needed
         // but Context and Builder manipulate global variables
         // bad LLVM design
@@ -251,53 +657,110 @@ impl<'a, 'c> FuncBuild<'a, 'c> {
             None => builder.position_at_end(self.entry),
         }
 
-        builder.build_alloca(self.context().f64_type(), name)
+        builder.build_alloca(ty, name)
     }
 
-    fn build_expr(&mut self, expr: &ast::Expr<'c>) -> FloatValue<'c> {
+    fn build_expr(&mut self, expr: &ast::Expr<'c>) -> BuildResult<BasicValueEnum<'c>> {
         match &*expr.kind {
             // Place Can only be used in function calls
             // ExprKind::Place(_) => unreachable!("Place cannot be a valid expr yet..."),
-            ExprKind::Var(td) => {
-                let sid = match self.state.mod_syms.lookup(&expr.nid) {
-                    Some(s) => s,
-                    None => unimplemented!("NodeId => SymId lookup failed"),
-                };
-
-                self.build_variable(sid, td.span)
-            }
-            ExprKind::Binary { op, lhs, rhs } => self.build_binary_expr(*op, lhs, rhs),
-            ExprKind::Lit(flp) => self.build_literal(flp),
+            ExprKind::Var(td) => match self.variant_of(expr)? {
+                Some(variant) if !variant.payload.is_empty() => Err(CompileError::VariantAsValue {
+                    name: String::from(td.span),
+                    span: expr.span,
+                }),
+                Some(variant) => self.build_variant(variant, &[]),
+                None => {
+                    let sid = self.lookup_sym(&expr.nid, expr.span)?;
+                    self.build_variable(&sid, td)
+                }
+            },
+            ExprKind::Binary { op, lhs, rhs } => self.build_binary_expr(*op, lhs, rhs, expr.span),
+            ExprKind::Unary { op, operand } => self.build_unary_expr(*op, operand),
+            ExprKind::Lit(lit) => self.build_literal(lit, &self.expr_type(expr)?, expr.span),
             ExprKind::Block(block) => {
-                let mut val = self.context().f64_type().const_float(0.0);
+                let mut val = self.nil_value();
                 for expr in block {
-                    val = self.build_expr(expr);
+                    val = self.build_expr(expr)?;
+                }
+                Ok(val)
+            }
+            ExprKind::Call { callee, args } => {
+                if let Some(builtin) = self.builtin_of(callee) {
+                    return self.build_builtin(builtin, args);
+                }
+                match self.variant_of(callee)? {
+                    Some(variant) => self.build_variant(variant, args),
+                    None => self.build_call(callee, args),
                 }
-                val
             }
-            ExprKind::Call { callee, args } => self.build_call(callee, args),
+            ExprKind::ArrayLit(elems) => self.build_array_lit(expr, elems),
+            ExprKind::Index { base, index } => {
+                let ptr = self.build_element_ptr(base, index)?;
+                Ok(self.builder().build_load(ptr, "elem"))
+            }
+            ExprKind::StructLit { fields, .. } => self.build_struct_lit(expr, fields),
+            ExprKind::TupleLit(elems) => self.build_tuple_lit(expr, elems),
+            ExprKind::TupleField { base, index } => {
+                let base_val = self.build_expr(base)?.into_struct_value();
+                let val = self
+                    .builder()
+                    .build_extract_value(base_val, *index as u32, "elem");
+                Ok(val.unwrap())
+            }
+            ExprKind::Field { base, field } => {
+                let idx = self.state.field_index(&self.expr_type(base)?, field)?;
+                let base_val = self.build_expr(base)?.into_struct_value();
+                let val = self
+                    .builder()
+                    .build_extract_value(base_val, idx, field.span);
+                Ok(val.unwrap())
+            }
             ExprKind::If {
                 cond,
                 if_body,
                 else_body,
-            } => self.build_if_expr(cond, if_body, else_body),
-            // TODO: not well defined behavior returning this statment
+            } => self.build_if_expr(expr, cond, if_body, else_body),
             ExprKind::Decl(vd) => {
-                use std::f64;
-                self.build_var_decl(&expr.nid, vd);
-                self.context().f64_type().const_float(f64::NAN)
+                self.build_var_decl(expr, vd)?;
+                Ok(self.nil_value())
+            }
+            ExprKind::Destructure {
+                mutable,
+                pat,
+                value,
+                ..
+            } => {
+                let val = self.build_expr(value)?;
+                self.build_pattern_bind(pat, val, *mutable)?;
+                Ok(self.nil_value())
             }
             ExprKind::Let { bound, let_body } => self.build_let_expr(bound, let_body),
 
-            // TODO: behaviro not well defined
             ExprKind::While { cond, while_body } => {
-                self.build_while_stmt(cond, while_body);
-                self.context().f64_type().const_float(f64::NAN)
-            } // _ => unimplemented!()
+                self.build_while_stmt(cond, while_body)?;
+                Ok(self.nil_value())
+            }
+            ExprKind::Loop(loop_body) => self.build_loop_expr(expr, loop_body),
+            ExprKind::For { range, for_body } => {
+                self.build_for_stmt(range, for_body, expr.span)?;
+                Ok(self.nil_value())
+            }
+            ExprKind::Match { scrutinee, arms } => self.build_match_expr(expr, scrutinee, arms),
+            ExprKind::Break(_) | ExprKind::Continue => self.build_jump(expr),
+            ExprKind::Return(value) => self.build_return_expr(expr, value),
+            // the front-end stops before codegen when the parser recovered
+            ExprKind::Error => Err(CompileError::UnresolvedNode(expr.span)),
+            // _ => unimplemented!()
         }
     }
 
-    fn build_while_stmt(&mut self, cond: &ast::Expr<'c>, body: &ast::Expr<'c>) {
+    // conditions are already `bool`, which is an i1
+    fn build_cond(&mut self, cond: &ast::Expr<'c>) -> BuildResult<IntValue<'c>> {
+        Ok(self.build_expr(cond)?.into_int_value())
+    }
+
+    fn build_while_stmt(&mut self, cond: &ast::Expr<'c>, body: &ast::Expr<'c>) -> BuildResult<()> {
         // create basic block
         let while_cond_block = self.context().append_basic_block(self.func, "while.cond");
         let while_body_block = self.context().append_basic_block(self.func, "while.body");
@@ -308,54 +771,628 @@ impl<'a, 'c> FuncBuild<'a, 'c> {
 
         // build conditional LLVM
         self.builder().position_at_end(while_cond_block);
-        let zero_const = self.context().f64_type().const_float(0.0);
-        let built_cond = self.build_expr(cond);
-        let truth_switch = self.builder().build_float_compare(
-            FloatPredicate::ONE,
-            built_cond,
-            zero_const,
-            "while.switch",
-        );
+        let truth_switch = self.build_cond(cond)?;
 
         self.builder()
             .build_conditional_branch(truth_switch, while_body_block, while_after_block);
 
         self.builder().position_at_end(while_body_block);
-        self.build_expr(body);
+        self.loops.push(LoopTargets {
+            next: while_cond_block,
+            exit: while_after_block,
+            breaks: None,
+        });
+        let body_res = self.build_expr(body);
+        self.loops.pop();
+        body_res?;
 
         // loop back to top
         self.builder().build_unconditional_branch(while_cond_block);
 
         // next stmts append to this bb
         self.builder().position_at_end(while_after_block);
+        Ok(())
+    }
+
+    fn build_loop_expr(
+        &mut self,
+        loop_expr: &ast::Expr<'c>,
+        body: &ast::Expr<'c>,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let loop_body_block = self.context().append_basic_block(self.func, "loop.body");
+        let loop_after_block = self.context().append_basic_block(self.func, "loop.after");
+
+        self.builder().build_unconditional_branch(loop_body_block);
+
+        self.builder().position_at_end(loop_body_block);
+        self.loops.push(LoopTargets {
+            next: loop_body_block,
+            exit: loop_after_block,
+            breaks: Some(Vec::new()),
+        });
+        let body_res = self.build_expr(body);
+        let targets = self.loops.pop().unwrap();
+        body_res?;
+
+        // loop back to top
+        self.builder().build_unconditional_branch(loop_body_block);
+
+        // the loop's value is whichever `break` left it
+        self.builder().position_at_end(loop_after_block);
+        let loop_type = self.state.llvm_type(&self.expr_type(loop_expr)?);
+        let breaks = targets.breaks.unwrap_or_default();
+        if breaks.is_empty() {
+            // never left, nothing reaches this block
+            return Ok(undef_value(loop_type));
+        }
+
+        let incoming: Vec<(&dyn BasicValue<'c>, BasicBlock<'c>)> = breaks
+            .iter()
+            .map(|(val, bb)| (val as &dyn BasicValue<'c>, *bb))
+            .collect();
+        let phi_node = self.builder().build_phi(loop_type, "loop_phi");
+        phi_node.add_incoming(&incoming);
+
+        Ok(phi_node.as_basic_value())
     }
 
-    fn build_var_decl(&mut self, nid: &NodeId, vd: &ast::VarDecl<'c>) {
-        let sid = match self.state.mod_syms.lookup(nid) {
-            Some(s) => *s,
-            None => unimplemented!("NodeId => SymId lookup failed"),
+    fn build_for_stmt(
+        &mut self,
+        range: &ast::ForRange<'c>,
+        body: &ast::Expr<'c>,
+        span: SrcSpan,
+    ) -> BuildResult<()> {
+        // bounds and step are evaluated once, before the loop
+        let counter_ty = self.expr_type(&range.start)?;
+        let llvm_ty = self.state.llvm_type(&counter_ty);
+        let start_val = self.build_expr(&range.start)?;
+        let end_val = self.build_expr(&range.end)?;
+        let step_val = match &range.step {
+            Some(step) => self.build_expr(step)?,
+            None if counter_ty.is_float() => llvm_ty.into_float_type().const_float(1.0).into(),
+            None => llvm_ty.into_int_type().const_int(1, false).into(),
+        };
+        let preheader = self.builder().get_insert_block().unwrap();
+
+        let for_cond_block = self.context().append_basic_block(self.func, "for.cond");
+        let for_body_block = self.context().append_basic_block(self.func, "for.body");
+        let for_next_block = self.context().append_basic_block(self.func, "for.next");
+        let for_after_block = self.context().append_basic_block(self.func, "for.after");
+
+        self.builder().build_unconditional_branch(for_cond_block);
+
+        // the counter is a phi of the start and the stepped value
+        self.builder().position_at_end(for_cond_block);
+        let counter = self.builder().build_phi(llvm_ty, range.var.span);
+        counter.add_incoming(&[(&start_val, preheader)]);
+        let counter_val = counter.as_basic_value();
+
+        let in_range = match &range.step {
+            None => self
+                .build_typed_binary(BinaryOp::Lt, &counter_ty, counter_val, end_val, span)?
+                .into_int_value(),
+            Some(_) => {
+                self.build_step_in_range(&counter_ty, counter_val, end_val, step_val, span)?
+            }
+        };
+        self.builder()
+            .build_conditional_branch(in_range, for_body_block, for_after_block);
+
+        self.builder().position_at_end(for_body_block);
+        let sid = self.lookup_sym(&range.var_nid, range.var.src_span())?;
+        self.var_sym.insert(sid, VarSlot::Value(counter_val));
+        self.loops.push(LoopTargets {
+            next: for_next_block,
+            exit: for_after_block,
+            breaks: None,
+        });
+        let body_res = self.build_expr(body);
+        self.loops.pop();
+        body_res?;
+
+        self.builder().build_unconditional_branch(for_next_block);
+
+        // step and loop back to top
+        self.builder().position_at_end(for_next_block);
+        let stepped =
+            self.build_typed_binary(BinaryOp::Add, &counter_ty, counter_val, step_val, span)?;
+        self.builder().build_unconditional_branch(for_cond_block);
+        counter.add_incoming(&[(&stepped, for_next_block)]);
+
+        // next stmts append to this bb
+        self.builder().position_at_end(for_after_block);
+        Ok(())
+    }
+
+    // a positive step counts up to before the end, a negative one down to after it,
+    // a zero step never enters the body
+    fn build_step_in_range(
+        &mut self,
+        counter_ty: &Type,
+        counter_val: BasicValueEnum<'c>,
+        end_val: BasicValueEnum<'c>,
+        step_val: BasicValueEnum<'c>,
+        span: SrcSpan,
+    ) -> BuildResult<IntValue<'c>> {
+        let llvm_ty = self.state.llvm_type(counter_ty);
+        let zero: BasicValueEnum = if counter_ty.is_float() {
+            llvm_ty.into_float_type().const_float(0.0).into()
+        } else {
+            llvm_ty.into_int_type().const_int(0, false).into()
         };
 
+        let counts_up = self
+            .build_typed_binary(BinaryOp::Gt, counter_ty, step_val, zero, span)?
+            .into_int_value();
+        let counts_down = self
+            .build_typed_binary(BinaryOp::Lt, counter_ty, step_val, zero, span)?
+            .into_int_value();
+        let below_end = self
+            .build_typed_binary(BinaryOp::Lt, counter_ty, counter_val, end_val, span)?
+            .into_int_value();
+        let above_end = self
+            .build_typed_binary(BinaryOp::Gt, counter_ty, counter_val, end_val, span)?
+            .into_int_value();
+
+        let stopped = self.context().bool_type().const_zero();
+        let down = self
+            .builder()
+            .build_select(counts_down, above_end, stopped, "for.down")
+            .into_int_value();
+        let in_range = self
+            .builder()
+            .build_select(counts_up, below_end, down, "for.in_range")
+            .into_int_value();
+        Ok(in_range)
+    }
+
+    fn build_match_expr(
+        &mut self,
+        match_expr: &ast::Expr<'c>,
+        scrutinee: &ast::Expr<'c>,
+        arms: &[ast::MatchArm<'c>],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let scrutinee_ty = self.expr_type(scrutinee)?;
+        let scrutinee_val = self.build_expr(scrutinee)?;
+        let is_tuple = matches!(scrutinee_ty.kind(), TypeKind::Tuple(_));
+        if self.state.enums.is_enum(&scrutinee_ty) || is_tuple {
+            return self.build_nested_match(match_expr, scrutinee_val, &scrutinee_ty, arms);
+        }
+
+        let arm_blocks: Vec<_> = arms
+            .iter()
+            .map(|_| self.context().append_basic_block(self.func, "match.arm"))
+            .collect();
+        // exhaustiveness checking makes sure some arm matches
+        let none_block = self.context().append_basic_block(self.func, "match.none");
+        let after_block = self.context().append_basic_block(self.func, "match.after");
+
+        // arms after one matching everything are never tested
+        let mut cases = Vec::new();
+        let mut default_block = none_block;
+        for (arm, block) in arms.iter().zip(&arm_blocks) {
+            match &arm.pat.kind {
+                PatternKind::Lit(lit) => {
+                    let val = self.build_literal(lit, &scrutinee_ty, arm.pat.span)?;
+                    cases.push((val, *block));
+                }
+                PatternKind::Wildcard | PatternKind::Bind(_) => {
+                    default_block = *block;
+                    break;
+                }
+                PatternKind::Variant { .. } | PatternKind::Tuple(_) => {
+                    unreachable!("nested patterns are only type checked against enums and tuples")
+                }
+            }
+        }
+
+        if cases.is_empty() {
+            self.builder().build_unconditional_branch(default_block);
+        } else if scrutinee_ty.is_float() {
+            self.build_float_cases(scrutinee_val, &cases, default_block);
+        } else {
+            // a repeated value belongs to its first arm
+            let mut switch_cases: Vec<(IntValue<'c>, BasicBlock<'c>)> = Vec::new();
+            for (val, block) in cases {
+                let val = val.into_int_value();
+                if !switch_cases.iter().any(|(prev, _)| *prev == val) {
+                    switch_cases.push((val, block));
+                }
+            }
+            self.builder().build_switch(
+                scrutinee_val.into_int_value(),
+                default_block,
+                &switch_cases,
+            );
+        }
+
+        self.builder().position_at_end(none_block);
+        self.builder().build_unreachable();
+
+        let mut incoming = Vec::with_capacity(arms.len());
+        for (arm, block) in arms.iter().zip(arm_blocks) {
+            self.builder().position_at_end(block);
+            if let PatternKind::Bind(name) = &arm.pat.kind {
+                let sid = self.lookup_sym(&arm.pat.nid, name.src_span())?;
+                self.var_sym.insert(sid, VarSlot::Value(scrutinee_val));
+            }
+
+            let val = self.build_expr(&arm.body)?;
+            self.builder().build_unconditional_branch(after_block);
+            incoming.push((val, self.builder().get_insert_block().unwrap()));
+        }
+
+        self.builder().position_at_end(after_block);
+        self.build_match_phi(match_expr, &incoming)
+    }
+
+    // after match, merge value
+    fn build_match_phi(
+        &mut self,
+        match_expr: &ast::Expr<'c>,
+        incoming: &[(BasicValueEnum<'c>, BasicBlock<'c>)],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let match_type = self.state.llvm_type(&self.expr_type(match_expr)?);
+        if incoming.is_empty() {
+            return Ok(undef_value(match_type));
+        }
+
+        let incoming: Vec<(&dyn BasicValue<'c>, BasicBlock<'c>)> = incoming
+            .iter()
+            .map(|(val, bb)| (val as &dyn BasicValue<'c>, *bb))
+            .collect();
+        let phi_node = self.builder().build_phi(match_type, "match_phi");
+        phi_node.add_incoming(&incoming);
+
+        Ok(phi_node.as_basic_value())
+    }
+
+    // payloads and elements can nest patterns, so each arm is tested in turn
+    fn build_nested_match(
+        &mut self,
+        match_expr: &ast::Expr<'c>,
+        scrutinee_val: BasicValueEnum<'c>,
+        scrutinee_ty: &Type,
+        arms: &[ast::MatchArm<'c>],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        // exhaustiveness checking makes sure some arm matches
+        let none_block = self.context().append_basic_block(self.func, "match.none");
+        let after_block = self.context().append_basic_block(self.func, "match.after");
+
+        let mut incoming = Vec::with_capacity(arms.len());
+        for (idx, arm) in arms.iter().enumerate() {
+            let next_block = if idx + 1 < arms.len() {
+                self.context().append_basic_block(self.func, "match.test")
+            } else {
+                none_block
+            };
+
+            self.build_pattern_test(&arm.pat, scrutinee_val, scrutinee_ty, next_block)?;
+            let val = self.build_expr(&arm.body)?;
+            self.builder().build_unconditional_branch(after_block);
+            incoming.push((val, self.builder().get_insert_block().unwrap()));
+
+            self.builder().position_at_end(next_block);
+        }
+
+        self.builder().position_at_end(none_block);
+        self.builder().build_unreachable();
+
+        self.builder().position_at_end(after_block);
+        self.build_match_phi(match_expr, &incoming)
+    }
+
+    // continues where `val` matches `pat` with its names bound,
+    // jumps to `fail` otherwise
+    fn build_pattern_test(
+        &mut self,
+        pat: &ast::Pattern<'c>,
+        val: BasicValueEnum<'c>,
+        ty: &Type,
+        fail: BasicBlock<'c>,
+    ) -> BuildResult<()> {
+        match &pat.kind {
+            PatternKind::Wildcard => Ok(()),
+            PatternKind::Bind(name) => match self.state.enums.variant(name.span) {
+                Some(variant) => self.build_tag_test(val, variant, fail),
+                None => {
+                    let sid = self.lookup_sym(&pat.nid, name.src_span())?;
+                    self.var_sym.insert(sid, VarSlot::Value(val));
+                    Ok(())
+                }
+            },
+            PatternKind::Lit(lit) => {
+                let lit_val = self.build_literal(lit, ty, pat.span)?;
+                let is_equal = if ty.is_float() {
+                    self.builder().build_float_compare(
+                        FloatPredicate::OEQ,
+                        val.into_float_value(),
+                        lit_val.into_float_value(),
+                        "tmpcase",
+                    )
+                } else {
+                    self.builder().build_int_compare(
+                        IntPredicate::EQ,
+                        val.into_int_value(),
+                        lit_val.into_int_value(),
+                        "tmpcase",
+                    )
+                };
+                self.build_test_branch(is_equal, fail);
+                Ok(())
+            }
+            PatternKind::Variant { name, args } => {
+                let variant = match self.state.enums.variant(name.span) {
+                    Some(variant) => variant,
+                    None => return Err(CompileError::UnresolvedNode(name.src_span())),
+                };
+                self.build_tag_test(val, variant, fail)?;
+
+                let payload = self.build_payload(val, variant);
+                for (idx, (arg, arg_ty)) in args.iter().zip(&variant.payload).enumerate() {
+                    let arg_val = self
+                        .builder()
+                        .build_extract_value(payload, idx as u32, "payload")
+                        .unwrap();
+                    self.build_pattern_test(arg, arg_val, arg_ty, fail)?;
+                }
+                Ok(())
+            }
+            PatternKind::Tuple(elems) => {
+                let elem_tys = match ty.kind() {
+                    TypeKind::Tuple(elem_tys) => elem_tys,
+                    _ => unreachable!("tuple patterns are only type checked against tuples"),
+                };
+                let tuple = val.into_struct_value();
+                for (idx, (elem, elem_ty)) in elems.iter().zip(elem_tys).enumerate() {
+                    let elem_val = self
+                        .builder()
+                        .build_extract_value(tuple, idx as u32, "elem")
+                        .unwrap();
+                    self.build_pattern_test(elem, elem_val, elem_ty, fail)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // names of a `val` pattern, match checking made sure it cannot fail
+    fn build_pattern_bind(
+        &mut self,
+        pat: &ast::Pattern<'c>,
+        val: BasicValueEnum<'c>,
+        mutable: ast::MutKind,
+    ) -> BuildResult<()> {
+        match &pat.kind {
+            PatternKind::Bind(name) if self.state.enums.variant(name.span).is_none() => {
+                let sid = self.lookup_sym(&pat.nid, name.src_span())?;
+                let slot = match mutable {
+                    ast::MutKind::Mutable => {
+                        let ptr = self.create_entry_block_alloca(name.span, val.get_type());
+                        self.builder().build_store(ptr, val);
+                        VarSlot::Stack(ptr)
+                    }
+                    ast::MutKind::Const => VarSlot::Value(val),
+                };
+                self.var_sym.insert(sid, slot);
+            }
+            PatternKind::Tuple(elems) => {
+                let tuple = val.into_struct_value();
+                for (idx, elem) in elems.iter().enumerate() {
+                    let elem_val = self
+                        .builder()
+                        .build_extract_value(tuple, idx as u32, "elem")
+                        .unwrap();
+                    self.build_pattern_bind(elem, elem_val, mutable)?;
+                }
+            }
+            // the only variant of its enum
+            PatternKind::Variant { name, args } => {
+                let variant = match self.state.enums.variant(name.span) {
+                    Some(variant) => variant,
+                    None => return Err(CompileError::UnresolvedNode(name.src_span())),
+                };
+                let payload = self.build_payload(val, variant);
+                for (idx, arg) in args.iter().enumerate() {
+                    let arg_val = self
+                        .builder()
+                        .build_extract_value(payload, idx as u32, "payload")
+                        .unwrap();
+                    self.build_pattern_bind(arg, arg_val, mutable)?;
+                }
+            }
+            PatternKind::Bind(_) | PatternKind::Wildcard | PatternKind::Lit(_) => (),
+        }
+        Ok(())
+    }
+
+    fn build_tag_test(
+        &mut self,
+        val: BasicValueEnum<'c>,
+        variant: &VariantInfo,
+        fail: BasicBlock<'c>,
+    ) -> BuildResult<()> {
+        let tag = self
+            .builder()
+            .build_extract_value(val.into_struct_value(), 0, "tag")
+            .unwrap()
+            .into_int_value();
+        let expected = self
+            .context()
+            .i32_type()
+            .const_int(variant.tag as u64, false);
+        let is_variant =
+            self.builder()
+                .build_int_compare(IntPredicate::EQ, tag, expected, "tmptag");
+        self.build_test_branch(is_variant, fail);
+        Ok(())
+    }
+
+    fn build_test_branch(&mut self, matched: IntValue<'c>, fail: BasicBlock<'c>) {
+        let then_block = self.context().append_basic_block(self.func, "match.then");
+        self.builder()
+            .build_conditional_branch(matched, then_block, fail);
+        self.builder().position_at_end(then_block);
+    }
+
+    // the payload is read back as the struct of its variant
+    fn build_payload(&mut self, val: BasicValueEnum<'c>, variant: &VariantInfo) -> StructValue<'c> {
+        let slot = self.create_entry_block_alloca(&variant.name, val.get_type());
+        self.builder().build_store(slot, val);
+        let payload_ptr = self.build_payload_ptr(slot, variant);
+        let payload = self.builder().build_load(payload_ptr, "payload");
+        payload.into_struct_value()
+    }
+
+    fn build_payload_ptr(&self, slot: PointerValue<'c>, variant: &VariantInfo) -> PointerValue<'c> {
+        let words_ptr = self.builder().build_struct_gep(slot, 1, "payload").unwrap();
+        let variant_ptr = self
+            .state
+            .variant_type(variant)
+            .ptr_type(AddressSpace::Generic);
+        self.builder()
+            .build_pointer_cast(words_ptr, variant_ptr, &variant.name)
+    }
+
+    // a variant constructor, unless a local hides its name
+    fn variant_of(&self, expr: &ast::Expr<'c>) -> BuildResult<Option<&'a VariantInfo>> {
+        let name = match &*expr.kind {
+            ExprKind::Var(td) => td.span,
+            _ => return Ok(None),
+        };
+        let sid = self.lookup_sym(&expr.nid, expr.span)?;
+        if self.var_sym.contains_key(&sid) {
+            return Ok(None);
+        }
+        Ok(self.state.enums.variant(name))
+    }
+
+    // payload values are evaluated in the order they are written
+    fn build_variant(
+        &mut self,
+        variant: &VariantInfo,
+        args: &[ast::Expr<'c>],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let enum_ty = self.state.enum_types[variant.enum_name.as_str()];
+        let slot = self.create_entry_block_alloca(&variant.name, enum_ty.into());
+
+        let tag_ptr = self.builder().build_struct_gep(slot, 0, "tag").unwrap();
+        let tag = self
+            .context()
+            .i32_type()
+            .const_int(variant.tag as u64, false);
+        self.builder().build_store(tag_ptr, tag);
+
+        if !args.is_empty() {
+            let payload_ptr = self.build_payload_ptr(slot, variant);
+            for (idx, arg) in args.iter().enumerate() {
+                let val = self.build_expr(arg)?;
+                let field_ptr = self
+                    .builder()
+                    .build_struct_gep(payload_ptr, idx as u32, "payload")
+                    .unwrap();
+                self.builder().build_store(field_ptr, val);
+            }
+        }
+
+        Ok(self.builder().build_load(slot, &variant.name))
+    }
+
+    // floats cannot be switched on, each value is compared in turn
+    fn build_float_cases(
+        &mut self,
+        scrutinee_val: BasicValueEnum<'c>,
+        cases: &[(BasicValueEnum<'c>, BasicBlock<'c>)],
+        default_block: BasicBlock<'c>,
+    ) {
+        for (val, block) in cases {
+            let is_equal = self.builder().build_float_compare(
+                FloatPredicate::OEQ,
+                scrutinee_val.into_float_value(),
+                val.into_float_value(),
+                "tmpcase",
+            );
+            let next_block = self.context().append_basic_block(self.func, "match.test");
+            self.builder()
+                .build_conditional_branch(is_equal, *block, next_block);
+            self.builder().position_at_end(next_block);
+        }
+        self.builder().build_unconditional_branch(default_block);
+    }
+
+    fn build_jump(&mut self, jump: &ast::Expr<'c>) -> BuildResult<BasicValueEnum<'c>> {
+        // a break value is computed before leaving the loop
+        let break_val = match &*jump.kind {
+            ExprKind::Break(Some(value)) => Some(self.build_expr(value)?),
+            ExprKind::Break(None) => Some(self.nil_value()),
+            _ => None,
+        };
+        let from_bb = self.builder().get_insert_block().unwrap();
+
+        let target = match (self.loops.last_mut(), break_val) {
+            (Some(targets), Some(val)) => {
+                if let Some(breaks) = &mut targets.breaks {
+                    breaks.push((val, from_bb));
+                }
+                targets.exit
+            }
+            (Some(targets), None) => targets.next,
+            (None, _) => return Err(CompileError::JumpOutsideLoop(jump.span)),
+        };
+        self.builder().build_unconditional_branch(target);
+        self.build_after_jump(jump)
+    }
+
+    fn build_return_expr(
+        &mut self,
+        ret: &ast::Expr<'c>,
+        value: &ast::Expr<'c>,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let val = self.build_expr(value)?;
+        self.build_return(val);
+        self.build_after_jump(ret)
+    }
+
+    // the current block is terminated,
+    // code after the jump is never run but still needs a block
+    fn build_after_jump(&mut self, jump: &ast::Expr<'c>) -> BuildResult<BasicValueEnum<'c>> {
+        let dead_block = self.context().append_basic_block(self.func, "jump.dead");
+        self.builder().position_at_end(dead_block);
+
+        // the jump fits any type, branches merging it need a value
+        let ty = self.state.llvm_type(&self.expr_type(jump)?);
+        Ok(undef_value(ty))
+    }
+
+    fn build_var_decl(&mut self, decl: &ast::Expr<'c>, vd: &ast::VarDecl<'c>) -> BuildResult<()> {
+        let sid = self.lookup_sym(&decl.nid, vd.bound.src_span())?;
+
         match vd.mutable {
             ast::MutKind::Mutable => {
                 // must alloc on stack
-                let ptr = self.create_entry_block_alloca(vd.bound.span);
-                self.var_sym.insert(sid, BasicValueEnum::PointerValue(ptr));
+                let ty = self.expr_type(&vd.value)?;
+                let ptr = self.create_entry_block_alloca(vd.bound.span, self.state.llvm_type(&ty));
+                self.var_sym.insert(sid, VarSlot::Stack(ptr));
 
-                let val = self.build_expr(&vd.value);
+                let val = self.build_expr(&vd.value)?;
                 self.builder().build_store(ptr, val);
             }
             ast::MutKind::Const => {
-                let val = self.build_expr(&vd.value);
-                self.var_sym.insert(sid, BasicValueEnum::FloatValue(val));
+                let val = self.build_expr(&vd.value)?;
+                self.var_sym.insert(sid, VarSlot::Value(val));
             }
         }
+        Ok(())
     }
 
-    fn build_let_expr(&mut self, bindings: &[ast::Expr<'c>], lb: &ast::Expr<'c>) -> FloatValue<'c> {
+    fn build_let_expr(
+        &mut self,
+        bindings: &[ast::Expr<'c>],
+        lb: &ast::Expr<'c>,
+    ) -> BuildResult<BasicValueEnum<'c>> {
         // declare const variables
         for decl in bindings {
-            self.build_expr(decl);
+            self.build_expr(decl)?;
         }
 
         // define body
@@ -364,20 +1401,14 @@ impl<'a, 'c> FuncBuild<'a, 'c> {
 
     fn build_if_expr(
         &mut self,
+        if_expr: &ast::Expr<'c>,
         cond: &ast::Expr<'c>,
         if_body: &ast::Expr<'c>,
         else_body: &ast::Expr<'c>,
-    ) -> FloatValue<'c> {
+    ) -> BuildResult<BasicValueEnum<'c>> {
         let parent = self.func;
-        let zero_const = self.context().f64_type().const_float(0.0);
-
-        let built_cond = self.build_expr(cond);
-        let truth_switch = self.builder().build_float_compare(
-            FloatPredicate::ONE,
-            built_cond,
-            zero_const,
-            "if.cond",
-        );
+
+        let truth_switch = self.build_cond(cond)?;
 
         let then_block = self.context().append_basic_block(parent, "if.then");
         let else_block = self.context().append_basic_block(parent, "if.else");
@@ -389,43 +1420,331 @@ impl<'a, 'c> FuncBuild<'a, 'c> {
 
         // then branch
         self.builder().position_at_end(then_block);
-        let then_val = self.build_expr(if_body);
+        let then_val = self.build_expr(if_body)?;
         self.builder().build_unconditional_branch(aif_block);
 
         let then_bb = self.builder().get_insert_block().unwrap();
 
         // else branch
         self.builder().position_at_end(else_block);
-        let else_val = self.build_expr(else_body);
+        let else_val = self.build_expr(else_body)?;
         self.builder().build_unconditional_branch(aif_block);
 
         let else_bb = self.builder().get_insert_block().unwrap();
 
         // after if, merge value
         self.builder().position_at_end(aif_block);
-        let phi_node = self
-            .builder()
-            .build_phi(self.context().f64_type(), "if_phi");
+        let if_type = self.state.llvm_type(&self.expr_type(if_expr)?);
+        let phi_node = self.builder().build_phi(if_type, "if_phi");
         phi_node.add_incoming(&[(&then_val, then_bb), (&else_val, else_bb)]);
 
-        phi_node.as_basic_value().into_float_value()
+        Ok(phi_node.as_basic_value())
     }
 
     fn build_assign_to_var(
         &mut self,
         sid: &SymId,
-        name: &'c str,
-        rhs_val: FloatValue<'c>,
-    ) -> FloatValue<'c> {
+        name: &ast::token::TokenData<'c>,
+        rhs_val: BasicValueEnum<'c>,
+    ) -> BuildResult<BasicValueEnum<'c>> {
         match self.var_sym.get(sid) {
-            Some(val) => match val {
-                BasicValueEnum::PointerValue(ptr) => {
-                    let _stored_instr = self.builder().build_store(*ptr, rhs_val);
-                    rhs_val
+            Some(VarSlot::Stack(ptr)) => {
+                let _stored_instr = self.builder().build_store(*ptr, rhs_val);
+                Ok(rhs_val)
+            }
+            // args and `val` bindings are SSA values
+            Some(VarSlot::Value(_)) => Err(CompileError::AssignToConst {
+                name: String::from(name.span),
+                span: name.src_span(),
+            }),
+            None => Err(CompileError::UnknownVariable {
+                name: String::from(name.span),
+                span: name.src_span(),
+            }),
+        }
+    }
+
+    // fields are evaluated in the order they are written
+    fn build_struct_lit(
+        &mut self,
+        lit: &ast::Expr<'c>,
+        fields: &[ast::FieldInit<'c>],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let ty = self.expr_type(lit)?;
+        let mut agg = self.state.llvm_type(&ty).into_struct_type().get_undef();
+        for init in fields {
+            let idx = self.state.field_index(&ty, &init.name)?;
+            let val = self.build_expr(&init.value)?;
+            agg = self
+                .builder()
+                .build_insert_value(agg, val, idx, init.name.span)
+                .unwrap()
+                .into_struct_value();
+        }
+        Ok(agg.into())
+    }
+
+    // elements are evaluated in the order they are written
+    fn build_tuple_lit(
+        &mut self,
+        lit: &ast::Expr<'c>,
+        elems: &[ast::Expr<'c>],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let ty = self.expr_type(lit)?;
+        let mut agg = self.state.llvm_type(&ty).into_struct_type().get_undef();
+        for (idx, elem) in elems.iter().enumerate() {
+            let val = self.build_expr(elem)?;
+            agg = self
+                .builder()
+                .build_insert_value(agg, val, idx as u32, "elem")
+                .unwrap()
+                .into_struct_value();
+        }
+        Ok(agg.into())
+    }
+
+    // elements are stored on the heap and never freed
+    fn build_array_lit(
+        &mut self,
+        lit: &ast::Expr<'c>,
+        elems: &[ast::Expr<'c>],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let ty = self.expr_type(lit)?;
+        let elem_ty = match ty.kind() {
+            TypeKind::Array(elem) => self.state.llvm_type(elem),
+            _ => return Err(CompileError::UntypedNode(lit.span)),
+        };
+        let data_ty = elem_ty.ptr_type(AddressSpace::Generic);
+        let i64_type = self.context().i64_type();
+        let len = i64_type.const_int(elems.len() as u64, false);
+
+        // one stack slot per literal, reused by every evaluation within a call
+        let data = if elems.is_empty() {
+            data_ty.const_null()
+        } else {
+            let storage_ty = elem_ty.array_type(elems.len() as u32);
+            let storage = self.create_entry_block_alloca("array", storage_ty.into());
+            self.builder().build_pointer_cast(storage, data_ty, "array")
+        };
+
+        for (idx, elem) in elems.iter().enumerate() {
+            let val = self.build_expr(elem)?;
+            let offset = i64_type.const_int(idx as u64, false);
+            let ptr = unsafe { self.builder().build_in_bounds_gep(data, &[offset], "elem") };
+            self.builder().build_store(ptr, val);
+        }
+
+        let slice = self.state.llvm_type(&ty).into_struct_type().get_undef();
+        let slice = self
+            .builder()
+            .build_insert_value(slice, data, 0, "array")
+            .unwrap()
+            .into_struct_value();
+        let slice = self
+            .builder()
+            .build_insert_value(slice, len, 1, "array")
+            .unwrap()
+            .into_struct_value();
+        Ok(slice.into())
+    }
+
+    // address of `base[index]`, after the bounds check
+    fn build_element_ptr(
+        &mut self,
+        base: &ast::Expr<'c>,
+        index: &ast::Expr<'c>,
+    ) -> BuildResult<PointerValue<'c>> {
+        let arr = self.build_expr(base)?.into_struct_value();
+        let index_val = self.build_expr(index)?.into_int_value();
+
+        // indices of any integer type are compared as i64
+        let i64_type = self.context().i64_type();
+        let index_val = match self.expr_type(index)?.kind() {
+            TypeKind::Int(w) if w.bits() < 64 => self
+                .builder()
+                .build_int_s_extend(index_val, i64_type, "index"),
+            TypeKind::UInt(w) if w.bits() < 64 => self
+                .builder()
+                .build_int_z_extend(index_val, i64_type, "index"),
+            _ => index_val,
+        };
+
+        if self.state.bounds_checks {
+            let len = self
+                .builder()
+                .build_extract_value(arr, 1, "len")
+                .unwrap()
+                .into_int_value();
+            self.build_bounds_check(index_val, len, index.span);
+        }
+
+        let data = self
+            .builder()
+            .build_extract_value(arr, 0, "data")
+            .unwrap()
+            .into_pointer_value();
+        Ok(unsafe {
+            self.builder()
+                .build_in_bounds_gep(data, &[index_val], "elem")
+        })
+    }
+
+    // prints where the index was out of range to stderr and aborts
+    fn build_bounds_check(&mut self, index: IntValue<'c>, len: IntValue<'c>, span: SrcSpan) {
+        // a negative index is a huge unsigned one
+        let in_bounds = self
+            .builder()
+            .build_int_compare(IntPredicate::ULT, index, len, "inbounds");
+        self.build_runtime_check(
+            in_bounds,
+            "bounds",
+            "index %lld is out of bounds for length %lld",
+            &[index.into(), len.into()],
+            span,
+        );
+    }
+
+    // division by zero and `MIN / -1` are undefined in LLVM
+    fn build_division_check(
+        &mut self,
+        lhs_val: IntValue<'c>,
+        rhs_val: IntValue<'c>,
+        unsigned: bool,
+        span: SrcSpan,
+    ) {
+        let int_ty = rhs_val.get_type();
+        let nonzero = self.builder().build_int_compare(
+            IntPredicate::NE,
+            rhs_val,
+            int_ty.const_zero(),
+            "nonzero",
+        );
+        self.build_runtime_check(nonzero, "div", "division by zero", &[], span);
+
+        if !unsigned {
+            let min = int_ty.const_int(1u64 << (int_ty.get_bit_width() - 1), false);
+            let not_min =
+                self.builder()
+                    .build_int_compare(IntPredicate::NE, lhs_val, min, "notmin");
+            let minus_one = int_ty.const_all_ones();
+            let not_minus_one = self.builder().build_int_compare(
+                IntPredicate::NE,
+                rhs_val,
+                minus_one,
+                "notminusone",
+            );
+            let no_overflow = self
+                .builder()
+                .build_or(not_min, not_minus_one, "nooverflow");
+            self.build_runtime_check(no_overflow, "div", "division overflows", &[], span);
+        }
+    }
+
+    // prints `msg` with the source location and aborts unless `ok` holds
+    // `msg` is a printf format for `args`
+    fn build_runtime_check(
+        &mut self,
+        ok: IntValue<'c>,
+        name: &str,
+        msg: &str,
+        args: &[BasicValueEnum<'c>],
+        span: SrcSpan,
+    ) {
+        let fail_block = self
+            .context()
+            .append_basic_block(self.func, &format!("{}.fail", name));
+        let ok_block = self
+            .context()
+            .append_basic_block(self.func, &format!("{}.ok", name));
+        self.builder()
+            .build_conditional_branch(ok, ok_block, fail_block);
+
+        self.builder().position_at_end(fail_block);
+        let i32_type = self.context().i32_type();
+        let i8_ptr = self.context().i8_type().ptr_type(AddressSpace::Generic);
+        let dprintf = self.state.runtime_func(
+            "dprintf",
+            i32_type.fn_type(&[i32_type.into(), i8_ptr.into()], true),
+        );
+        let abort = self
+            .state
+            .runtime_func("abort", self.context().void_type().fn_type(&[], false));
+
+        let msg = format!("{} at {}:{}\n", msg, span.start.line, span.start.column);
+        let fmt = self
+            .builder()
+            .build_global_string_ptr(&msg, &format!("{}_msg", name))
+            .as_pointer_value();
+        let stderr = i32_type.const_int(2, false);
+        let mut call_args = vec![stderr.into(), fmt.into()];
+        call_args.extend_from_slice(args);
+        self.builder().build_call(dprintf, &call_args, name);
+        self.builder().build_call(abort, &[], "abort");
+        self.builder().build_unreachable();
+
+        self.builder().position_at_end(ok_block);
+    }
+
+    // name resolution leaves builtins without a symbol
+    fn builtin_of(&self, callee: &ast::Expr<'c>) -> Option<Builtin> {
+        match &*callee.kind {
+            ExprKind::Var(td) if self.state.mod_syms.lookup(&callee.nid).is_none() => {
+                Builtin::from_name(td.span)
+            }
+            _ => None,
+        }
+    }
+
+    fn build_builtin(
+        &mut self,
+        builtin: Builtin,
+        args: &[ast::Expr<'c>],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        match builtin {
+            Builtin::Len => {
+                let arr = self.build_expr(&args[0])?.into_struct_value();
+                let len = self.builder().build_extract_value(arr, 1, "len");
+                Ok(len.unwrap())
+            }
+        }
+    }
+
+    // address of a field inside a `val mut` struct
+    fn build_field_ptr(&mut self, target: &ast::Expr<'c>) -> BuildResult<PointerValue<'c>> {
+        match &*target.kind {
+            ast::ExprKind::Var(td) => {
+                let sid = self.lookup_sym(&target.nid, target.span)?;
+                match self.var_sym.get(&sid) {
+                    Some(VarSlot::Stack(ptr)) => Ok(*ptr),
+                    Some(VarSlot::Value(_)) => Err(CompileError::AssignToConst {
+                        name: String::from(td.span),
+                        span: td.src_span(),
+                    }),
+                    None => Err(CompileError::UnknownVariable {
+                        name: String::from(td.span),
+                        span: td.src_span(),
+                    }),
                 }
-                _ => unimplemented!("Local Variable of invalid type",),
-            },
-            None => unimplemented!("Reference unknown variable {:?} in Assign", name),
+            }
+            ast::ExprKind::Field { base, field } => {
+                let idx = self.state.field_index(&self.expr_type(base)?, field)?;
+                let base_ptr = self.build_field_ptr(base)?;
+                self.builder()
+                    .build_struct_gep(base_ptr, idx, field.span)
+                    .map_err(|_| CompileError::UnknownField {
+                        name: String::from(field.span),
+                        span: field.src_span(),
+                    })
+            }
+            ast::ExprKind::TupleField { base, index } => {
+                let base_ptr = self.build_field_ptr(base)?;
+                self.builder()
+                    .build_struct_gep(base_ptr, *index as u32, "elem")
+                    .map_err(|_| CompileError::InvalidAssignTarget(target.span))
+            }
+            // elements live behind the array's pointer
+            ast::ExprKind::Index { base, index } => self.build_element_ptr(base, index),
+            _ => Err(CompileError::InvalidAssignTarget(target.span)),
         }
     }
 
@@ -434,117 +1753,461 @@ impl<'a, 'c> FuncBuild<'a, 'c> {
         op: BinaryOp,
         lhs: &ast::Expr<'c>,
         rhs: &ast::Expr<'c>,
-    ) -> FloatValue<'c> {
+        span: SrcSpan,
+    ) -> BuildResult<BasicValueEnum<'c>> {
         if op == BinaryOp::Assign {
-            let rhs_val = self.build_expr(rhs);
-            match &*lhs.kind {
+            let rhs_val = self.build_expr(rhs)?;
+            return match &*lhs.kind {
                 ast::ExprKind::Var(td) => {
-                    let sid = match self.state.mod_syms.lookup(&lhs.nid) {
-                        Some(s) => s,
-                        None => unimplemented!("NodeId => SymId lookup failed"),
-                    };
-                    return self.build_assign_to_var(sid, td.span, rhs_val);
+                    let sid = self.lookup_sym(&lhs.nid, lhs.span)?;
+                    self.build_assign_to_var(&sid, td, rhs_val)
+                }
+                ast::ExprKind::Field { .. } | ast::ExprKind::TupleField { .. } => {
+                    let ptr = self.build_field_ptr(lhs)?;
+                    self.builder().build_store(ptr, rhs_val);
+                    Ok(rhs_val)
                 }
-                _ => unimplemented!("Cannot assign to non-variable"),
-            }
-        }
-
-        let lhs_val = self.build_expr(lhs);
-        let rhs_val = self.build_expr(rhs);
-
-        match op {
-            BinaryOp::Add => self.builder().build_float_add(lhs_val, rhs_val, "tmpdadd"),
-            BinaryOp::Sub => self.builder().build_float_sub(lhs_val, rhs_val, "tmpdsub"),
-            BinaryOp::Mul => self.builder().build_float_mul(lhs_val, rhs_val, "tmpdmul"),
-            BinaryOp::Div => self.builder().build_float_div(lhs_val, rhs_val, "tmpddiv"),
-            BinaryOp::Lt => {
-                let bool_val = self.builder().build_float_compare(
-                    FloatPredicate::OLT,
-                    lhs_val,
-                    rhs_val,
-                    "tmpflt",
-                );
-                // using unsigned because
-                // signed 1-bit int only represents -1, 0
-                // but the program semantic require 0, 1
-                self.builder().build_unsigned_int_to_float(
-                    bool_val,
-                    self.context().f64_type(),
-                    "tmpbool",
-                )
-            }
-            BinaryOp::LtEt => {
-                let bool_val = self.builder().build_float_compare(
-                    FloatPredicate::OLE,
-                    lhs_val,
-                    rhs_val,
-                    "tmpfltet",
-                );
-                self.builder().build_unsigned_int_to_float(
-                    bool_val,
-                    self.context().f64_type(),
-                    "tmpbool",
-                )
-                // self.builder().build_signed_int_to_float(bool_val, self.context().f64_type(), "tmpbool")
+                ast::ExprKind::Index { base, index } => {
+                    let ptr = self.build_element_ptr(base, index)?;
+                    self.builder().build_store(ptr, rhs_val);
+                    Ok(rhs_val)
+                }
+                _ => Err(CompileError::InvalidAssignTarget(lhs.span)),
+            };
+        }
+        if op == BinaryOp::LogicalAnd || op == BinaryOp::LogicalOr {
+            return self.build_logical_expr(op, lhs, rhs);
+        }
+
+        // both sides have the same type after type checking
+        let operand_ty = self.expr_type(lhs)?;
+        let lhs_val = self.build_expr(lhs)?;
+        let rhs_val = self.build_expr(rhs)?;
+        self.build_typed_binary(op, &operand_ty, lhs_val, rhs_val, span)
+    }
+
+    fn build_typed_binary(
+        &mut self,
+        op: BinaryOp,
+        operand_ty: &Type,
+        lhs_val: BasicValueEnum<'c>,
+        rhs_val: BasicValueEnum<'c>,
+        span: SrcSpan,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        if operand_ty.is_float() {
+            self.build_float_binary(
+                op,
+                lhs_val.into_float_value(),
+                rhs_val.into_float_value(),
+                span,
+            )
+        } else {
+            self.build_int_binary(
+                op,
+                lhs_val.into_int_value(),
+                rhs_val.into_int_value(),
+                operand_ty.is_unsigned(),
+                span,
+            )
+        }
+    }
+
+    // rhs is only evaluated when lhs does not decide the result
+    fn build_logical_expr(
+        &mut self,
+        op: BinaryOp,
+        lhs: &ast::Expr<'c>,
+        rhs: &ast::Expr<'c>,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let lhs_val = self.build_cond(lhs)?;
+        let lhs_bb = self.builder().get_insert_block().unwrap();
+
+        let rhs_block = self.context().append_basic_block(self.func, "logic.rhs");
+        let after_block = self.context().append_basic_block(self.func, "logic.after");
+
+        if op == BinaryOp::LogicalAnd {
+            self.builder()
+                .build_conditional_branch(lhs_val, rhs_block, after_block);
+        } else {
+            self.builder()
+                .build_conditional_branch(lhs_val, after_block, rhs_block);
+        }
+
+        self.builder().position_at_end(rhs_block);
+        let rhs_val = self.build_cond(rhs)?;
+        self.builder().build_unconditional_branch(after_block);
+        let rhs_bb = self.builder().get_insert_block().unwrap();
+
+        // skipping rhs means lhs was the result
+        self.builder().position_at_end(after_block);
+        let phi_node = self
+            .builder()
+            .build_phi(self.context().bool_type(), "logic_phi");
+        phi_node.add_incoming(&[(&lhs_val, lhs_bb), (&rhs_val, rhs_bb)]);
+
+        Ok(phi_node.as_basic_value())
+    }
+
+    fn build_float_binary(
+        &mut self,
+        op: BinaryOp,
+        lhs_val: FloatValue<'c>,
+        rhs_val: FloatValue<'c>,
+        span: SrcSpan,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let val = match op {
+            BinaryOp::Add => self
+                .builder()
+                .build_float_add(lhs_val, rhs_val, "tmpdadd")
+                .into(),
+            BinaryOp::Sub => self
+                .builder()
+                .build_float_sub(lhs_val, rhs_val, "tmpdsub")
+                .into(),
+            BinaryOp::Mul => self
+                .builder()
+                .build_float_mul(lhs_val, rhs_val, "tmpdmul")
+                .into(),
+            BinaryOp::Div => self
+                .builder()
+                .build_float_div(lhs_val, rhs_val, "tmpddiv")
+                .into(),
+            BinaryOp::Rem => self
+                .builder()
+                .build_float_rem(lhs_val, rhs_val, "tmpdrem")
+                .into(),
+            BinaryOp::Lt => self
+                .builder()
+                .build_float_compare(FloatPredicate::OLT, lhs_val, rhs_val, "tmpflt")
+                .into(),
+            BinaryOp::LtEt => self
+                .builder()
+                .build_float_compare(FloatPredicate::OLE, lhs_val, rhs_val, "tmpfltet")
+                .into(),
+            BinaryOp::Gt => self
+                .builder()
+                .build_float_compare(FloatPredicate::OGT, lhs_val, rhs_val, "tmpfgt")
+                .into(),
+            BinaryOp::GtEt => self
+                .builder()
+                .build_float_compare(FloatPredicate::OGE, lhs_val, rhs_val, "tmpfgtet")
+                .into(),
+            BinaryOp::Et => self
+                .builder()
+                .build_float_compare(FloatPredicate::OEQ, lhs_val, rhs_val, "tmpfet")
+                .into(),
+            // unordered so NaN != NaN holds
+            BinaryOp::NotEt => self
+                .builder()
+                .build_float_compare(FloatPredicate::UNE, lhs_val, rhs_val, "tmpfnet")
+                .into(),
+
+            BinaryOp::Assign => unreachable!(),
+            other_op => return Err(CompileError::UnsupportedOp { op: other_op, span }),
+        };
+
+        Ok(val)
+    }
+
+    // ints, bools and chars, unsigned picks the unsigned instructions
+    fn build_int_binary(
+        &mut self,
+        op: BinaryOp,
+        lhs_val: IntValue<'c>,
+        rhs_val: IntValue<'c>,
+        unsigned: bool,
+        span: SrcSpan,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let pred = |signed, unsigned_pred| if unsigned { unsigned_pred } else { signed };
+        if let BinaryOp::Div | BinaryOp::Rem = op {
+            self.build_division_check(lhs_val, rhs_val, unsigned, span);
+        }
+        // shift amounts wrap at the bit width instead of giving poison
+        let rhs_val = match op {
+            BinaryOp::Shl | BinaryOp::Shr => {
+                let int_ty = rhs_val.get_type();
+                let mask = int_ty.const_int(u64::from(int_ty.get_bit_width() - 1), false);
+                self.builder().build_and(rhs_val, mask, "shamt")
             }
+            _ => rhs_val,
+        };
+        let val = match op {
+            BinaryOp::Add => self.builder().build_int_add(lhs_val, rhs_val, "tmpiadd"),
+            BinaryOp::Sub => self.builder().build_int_sub(lhs_val, rhs_val, "tmpisub"),
+            BinaryOp::Mul => self.builder().build_int_mul(lhs_val, rhs_val, "tmpimul"),
+            BinaryOp::Div if unsigned => self
+                .builder()
+                .build_int_unsigned_div(lhs_val, rhs_val, "tmpudiv"),
+            BinaryOp::Div => self
+                .builder()
+                .build_int_signed_div(lhs_val, rhs_val, "tmpidiv"),
+            BinaryOp::Rem if unsigned => self
+                .builder()
+                .build_int_unsigned_rem(lhs_val, rhs_val, "tmpurem"),
+            BinaryOp::Rem => self
+                .builder()
+                .build_int_signed_rem(lhs_val, rhs_val, "tmpirem"),
+            BinaryOp::BitAnd => self.builder().build_and(lhs_val, rhs_val, "tmpand"),
+            BinaryOp::BitOr => self.builder().build_or(lhs_val, rhs_val, "tmpor"),
+            BinaryOp::BitXor => self.builder().build_xor(lhs_val, rhs_val, "tmpxor"),
+            BinaryOp::Shl => self.builder().build_left_shift(lhs_val, rhs_val, "tmpshl"),
+            // arithmetic shift keeps the sign of signed ints
+            BinaryOp::Shr => self
+                .builder()
+                .build_right_shift(lhs_val, rhs_val, !unsigned, "tmpshr"),
+            BinaryOp::Lt => self.builder().build_int_compare(
+                pred(IntPredicate::SLT, IntPredicate::ULT),
+                lhs_val,
+                rhs_val,
+                "tmpilt",
+            ),
+            BinaryOp::LtEt => self.builder().build_int_compare(
+                pred(IntPredicate::SLE, IntPredicate::ULE),
+                lhs_val,
+                rhs_val,
+                "tmpiltet",
+            ),
+            BinaryOp::Gt => self.builder().build_int_compare(
+                pred(IntPredicate::SGT, IntPredicate::UGT),
+                lhs_val,
+                rhs_val,
+                "tmpigt",
+            ),
+            BinaryOp::GtEt => self.builder().build_int_compare(
+                pred(IntPredicate::SGE, IntPredicate::UGE),
+                lhs_val,
+                rhs_val,
+                "tmpigtet",
+            ),
             BinaryOp::Et => {
-                let bool_val = self.builder().build_float_compare(
-                    FloatPredicate::OEQ,
-                    lhs_val,
-                    rhs_val,
-                    "tmpfet",
-                );
-                self.builder().build_unsigned_int_to_float(
-                    bool_val,
-                    self.context().f64_type(),
-                    "tmpbool",
-                )
+                self.builder()
+                    .build_int_compare(IntPredicate::EQ, lhs_val, rhs_val, "tmpiet")
+            }
+            BinaryOp::NotEt => {
+                self.builder()
+                    .build_int_compare(IntPredicate::NE, lhs_val, rhs_val, "tmpinet")
             }
 
             BinaryOp::Assign => unreachable!(),
-            other_op => unimplemented!("{:?} Not supported yet", other_op),
-        }
+            other_op => return Err(CompileError::UnsupportedOp { op: other_op, span }),
+        };
+
+        Ok(val.into())
     }
 
-    fn build_call(&mut self, callee: &ast::Expr<'c>, args: &[ast::Expr<'c>]) -> FloatValue<'c> {
-        let named_callee = match &*callee.kind {
-            ExprKind::Var(td) => td.span,
-            _ => unimplemented!("Cannot call function from general expression. must use a name"),
+    fn build_unary_expr(
+        &mut self,
+        op: UnaryOp,
+        operand: &ast::Expr<'c>,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let operand_ty = self.expr_type(operand)?;
+        let val = self.build_expr(operand)?;
+
+        let val = match op {
+            UnaryOp::Neg if operand_ty.is_float() => self
+                .builder()
+                .build_float_neg(val.into_float_value(), "tmpdneg")
+                .into(),
+            UnaryOp::Neg => self
+                .builder()
+                .build_int_neg(val.into_int_value(), "tmpineg")
+                .into(),
+            // bools are i1 so flipping every bit is enough
+            UnaryOp::LogicalNot => self
+                .builder()
+                .build_not(val.into_int_value(), "tmpnot")
+                .into(),
         };
 
-        match self.module().get_function(named_callee) {
-            Some(func) => {
-                let compiled_args: Vec<_> =
-                    args.iter().map(|e| self.build_expr(e).into()).collect();
-                let call_value =
-                    self.builder()
-                        .build_call(func, compiled_args.as_slice(), "tmpcall");
-
-                match call_value.try_as_basic_value().left() {
-                    Some(value) => value.into_float_value(),
-                    None => unimplemented!("Invalid function call in LLVM"),
-                }
-            }
+        Ok(val)
+    }
+
+    fn build_call(
+        &mut self,
+        callee: &ast::Expr<'c>,
+        args: &[ast::Expr<'c>],
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let fixed_args = match self.expr_type(callee)?.kind() {
+            TypeKind::Func { args, .. } => args.len(),
+            _ => return Err(CompileError::IndirectCall(callee.span)),
+        };
+
+        // functions named directly are called directly,
+        // any other callee is a pointer called indirectly
+        let func: CallableValue<'c> = match self.named_function(callee)? {
+            Some(func) => func.into(),
             None => {
-                unimplemented!("Unknown function call")
+                let ptr = self.build_expr(callee)?.into_pointer_value();
+                CallableValue::try_from(ptr).map_err(|_| CompileError::IndirectCall(callee.span))?
+            }
+        };
+
+        let mut compiled_args = Vec::with_capacity(args.len());
+        for (i, arg) in args.iter().enumerate() {
+            let val = self.build_expr(arg)?;
+            if i < fixed_args {
+                compiled_args.push(val);
+            } else {
+                compiled_args.push(self.promote_vararg(arg, val)?);
             }
         }
+
+        let call_value = self
+            .builder()
+            .build_call(func, compiled_args.as_slice(), "tmpcall");
+
+        // void functions return nil
+        match call_value.try_as_basic_value().left() {
+            Some(value) => Ok(value),
+            None => Ok(self.nil_value()),
+        }
+    }
+
+    // a function item named by `expr`, unless a local hides it
+    fn named_function(&self, expr: &ast::Expr<'c>) -> BuildResult<Option<FunctionValue<'c>>> {
+        let name = match &*expr.kind {
+            ExprKind::Var(td) => td.span,
+            _ => return Ok(None),
+        };
+        let sid = self.lookup_sym(&expr.nid, expr.span)?;
+        if self.var_sym.contains_key(&sid) {
+            return Ok(None);
+        }
+        match self.module().get_function(name) {
+            Some(func) => Ok(Some(func)),
+            None => Err(CompileError::UnknownFunction {
+                name: String::from(name),
+                span: expr.span,
+            }),
+        }
+    }
+
+    // C passes small ints as int and floats as double through `...`
+    fn promote_vararg(
+        &mut self,
+        arg: &ast::Expr<'c>,
+        val: BasicValueEnum<'c>,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let i32_type = self.context().i32_type();
+        let promoted = match self.expr_type(arg)?.kind() {
+            TypeKind::Float(FloatWidth::Float32) => self
+                .builder()
+                .build_float_ext(val.into_float_value(), self.context().f64_type(), "vararg")
+                .into(),
+            TypeKind::Int(w) if w.bits() < 32 => self
+                .builder()
+                .build_int_s_extend(val.into_int_value(), i32_type, "vararg")
+                .into(),
+            TypeKind::UInt(w) if w.bits() < 32 => self
+                .builder()
+                .build_int_z_extend(val.into_int_value(), i32_type, "vararg")
+                .into(),
+            TypeKind::Bool => self
+                .builder()
+                .build_int_z_extend(val.into_int_value(), i32_type, "vararg")
+                .into(),
+            _ => val,
+        };
+        Ok(promoted)
     }
 
-    fn build_variable(&mut self, sid: &SymId, name: &'c str) -> FloatValue<'c> {
+    fn build_variable(
+        &mut self,
+        sid: &SymId,
+        name: &ast::token::TokenData<'c>,
+    ) -> BuildResult<BasicValueEnum<'c>> {
         match self.var_sym.get(sid) {
-            Some(val) => match val {
-                BasicValueEnum::PointerValue(ptr) => {
-                    self.builder().build_load(*ptr, name).into_float_value()
-                }
-                BasicValueEnum::FloatValue(float) => *float,
-                _ => unimplemented!("Local Variable of invalid type",),
+            Some(VarSlot::Stack(ptr)) => Ok(self.builder().build_load(*ptr, name.span)),
+            Some(VarSlot::Value(val)) => Ok(*val),
+            // a function item is a pointer to its code
+            None => match self.module().get_function(name.span) {
+                Some(func) => Ok(func.as_global_value().as_pointer_value().into()),
+                None => Err(CompileError::UnknownVariable {
+                    name: String::from(name.span),
+                    span: name.src_span(),
+                }),
             },
-            None => unimplemented!("Reference unknown variable {:?}", name),
         }
     }
 
-    fn build_literal(&mut self, lit: &f64) -> FloatValue<'c> {
-        self.context().f64_type().const_float(*lit)
+    // literals take the type chosen by the type checker
+    fn build_literal(
+        &mut self,
+        lit: &Lit,
+        ty: &Type,
+        span: SrcSpan,
+    ) -> BuildResult<BasicValueEnum<'c>> {
+        let ty = self.state.llvm_type(ty);
+
+        let val = match (lit, ty) {
+            (Lit::Int(int), BasicTypeEnum::IntType(int_ty)) => {
+                int_ty.const_int(*int as u64, true).into()
+            }
+            (Lit::Int(int), BasicTypeEnum::FloatType(float_ty)) => {
+                float_ty.const_float(*int as f64).into()
+            }
+            (Lit::Float(float), BasicTypeEnum::FloatType(float_ty)) => {
+                float_ty.const_float(*float).into()
+            }
+            (Lit::Bool(b), BasicTypeEnum::IntType(int_ty)) => {
+                int_ty.const_int(*b as u64, false).into()
+            }
+            (Lit::Char(c), BasicTypeEnum::IntType(int_ty)) => {
+                int_ty.const_int(*c as u64, false).into()
+            }
+            // a private constant byte array with a null terminator
+            (Lit::Str(s), BasicTypeEnum::PointerType(_)) => self
+                .builder()
+                .build_global_string_ptr(s, "str")
+                .as_pointer_value()
+                .into(),
+            _ => return Err(CompileError::UntypedNode(span)),
+        };
+
+        Ok(val)
+    }
+}
+
+fn undef_value(ty: BasicTypeEnum) -> BasicValueEnum {
+    match ty {
+        BasicTypeEnum::ArrayType(t) => t.get_undef().into(),
+        BasicTypeEnum::FloatType(t) => t.get_undef().into(),
+        BasicTypeEnum::IntType(t) => t.get_undef().into(),
+        BasicTypeEnum::PointerType(t) => t.get_undef().into(),
+        BasicTypeEnum::StructType(t) => t.get_undef().into(),
+        BasicTypeEnum::VectorType(t) => t.get_undef().into(),
     }
 }
+
+#[test]
+fn test_compile_error_keeps_failed_function() {
+    use crate::cli::check::expect_checked;
+
+    let src = "mod m
+enum Shape Circle(f64) | Empty end
+fun shape()
+    val make = Circle
+    Empty
+end
+fun main(): f64
+    1.0
+end
+";
+    let checked = expect_checked(src);
+    let backend = LlvmBackend::default();
+    let (llvm_module, errs) = backend.build_mod(checked.module, &checked.sym_table, &checked.types);
+
+    assert_eq!(errs.len(), 1);
+    let diag = errs[0].to_diagnostic();
+    assert_eq!(diag.code, "E0912");
+    assert_eq!(
+        diag.msg,
+        "variant `Circle` cannot be used as a function value yet"
+    );
+    let start = diag.labels[0].span.start;
+    assert_eq!((start.line, start.column), (4, 16));
+
+    // callers may already point at it, so it stays until the module is dropped
+    assert!(llvm_module.get_function("shape").is_some());
+    assert!(llvm_module.get_function("main").is_some());
+}
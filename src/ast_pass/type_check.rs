@@ -0,0 +1,1520 @@
+use std::collections::HashMap;
+
+use crate::ast::ops::{BinaryOp, UnaryOp};
+use crate::ast::symbol::{ModSymTable, SymId};
+use crate::ast::token::{SrcSpan, TokenData};
+use crate::ast::{
+    Builtin, EnumTable, Expr, ExprKind, FloatWidth, FuncProto, Function, IntWidth, ItemKind, Lit,
+    Module, NodeId, Pattern, PatternKind, Type, TypeKind, TypeTable, TypeVar,
+};
+use crate::ast_pass::ModulePass;
+use crate::diag::{Diagnostic, ToDiagnostic};
+
+// Hindley-Milner style inference, runs after name resolution
+// 1. every node gets a type, unknown ones a fresh variable,
+//    and constraints between them are collected
+// 2. constraints are unified in source order
+// 3. variables nothing decided are defaulted,
+//    float literals to f64, integer literals to i64, anything else to f64
+// functions are not generalized, each one compiles to a single LLVM function
+pub struct AstTypeChecker<'a, 's> {
+    mst: &'a ModSymTable,
+    // type of every variable, argument and function
+    sym_types: HashMap<SymId, Type>,
+    // type of every node, may still contain variables
+    nodes: Vec<(NodeId, Type)>,
+    constraints: Vec<Constraint<'s>>,
+    // fallback types of literals
+    defaults: Vec<(Type, Type)>,
+    // value of every integer literal, checked against its type once solved
+    int_lits: Vec<(Type, i64, SrcSpan)>,
+    // `/ 0` and `% 0`, only an error once the operands are known to be integers
+    zero_divisors: Vec<(Type, SrcSpan)>,
+    unifier: Unifier,
+    errs: Vec<AstTypeErr<'s>>,
+    // name and return type of the function being checked
+    func_ret: Option<(TokenData<'s>, Type)>,
+    // innermost last, the value type of a `loop`, None for other loops
+    loops: Vec<Option<Type>>,
+    // field names and types of every struct, in declaration order
+    structs: HashMap<String, Vec<(String, Type)>>,
+    enums: EnumTable,
+}
+
+// what an operator or literal requires from a type
+// each bound accepts a subset of the one before it,
+// except Integer and Float which have nothing in common
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Bound {
+    // ==
+    Equatable,
+    // < and <=
+    Ordered,
+    // arithmetic and integer literals
+    Numeric,
+    // bitwise ops and shifts
+    Integer,
+    // float literals
+    Float,
+}
+
+impl Bound {
+    fn accepts(self, ty: &Type) -> bool {
+        match self {
+            // strings are pointers, `==` would compare addresses
+            Bound::Equatable => !matches!(
+                ty.kind(),
+                TypeKind::Func { .. }
+                    | TypeKind::Str
+                    | TypeKind::Nil
+                    | TypeKind::Named(_)
+                    | TypeKind::Array(_)
+                    | TypeKind::Tuple(_)
+            ),
+            Bound::Ordered => ty.is_numeric() || ty.kind() == &TypeKind::Char,
+            Bound::Numeric => ty.is_numeric(),
+            Bound::Integer => ty.is_int(),
+            Bound::Float => ty.is_float(),
+        }
+    }
+
+    // a type accepted by both, if there can be one
+    fn meet(self, other: Bound) -> Option<Bound> {
+        match (self, other) {
+            (Bound::Integer, Bound::Float) | (Bound::Float, Bound::Integer) => None,
+            _ => Some(self.max(other)),
+        }
+    }
+}
+
+enum Constraint<'s> {
+    // both types must be the same
+    Equal {
+        expected: Type,
+        found: Type,
+        span: SrcSpan,
+    },
+    // function body against the return type
+    Return {
+        func: TokenData<'s>,
+        expected: Type,
+        found: Type,
+        span: SrcSpan,
+    },
+    // operand of a built-in operator, kept as its symbol
+    Operand {
+        op: &'static str,
+        ty: Type,
+        bound: Bound,
+        span: SrcSpan,
+    },
+    Call {
+        callee: Type,
+        args: Vec<(Type, SrcSpan)>,
+        ret: Type,
+        span: SrcSpan,
+    },
+    // `base.field` has type `found`
+    Field {
+        base: Type,
+        field: TokenData<'s>,
+        found: Type,
+        span: SrcSpan,
+    },
+    // `base.0` has type `found`
+    Element {
+        base: Type,
+        index: usize,
+        found: Type,
+        span: SrcSpan,
+    },
+}
+
+// types are rendered before they are stored
+// so unknown numbers can be shown as `{number}`
+#[derive(Debug)]
+pub enum AstTypeErr<'s> {
+    Mismatch {
+        expected: String,
+        found: String,
+        span: SrcSpan,
+    },
+    // function body does not produce the return type
+    ReturnMismatch {
+        func: TokenData<'s>,
+        expected: String,
+        found: String,
+        span: SrcSpan,
+    },
+    InvalidOperand {
+        op: &'static str,
+        ty: String,
+        span: SrcSpan,
+    },
+    ArgCount {
+        expected: usize,
+        found: usize,
+        // at least `expected` arguments
+        variadic: bool,
+        span: SrcSpan,
+    },
+    NotCallable {
+        ty: String,
+        span: SrcSpan,
+    },
+    NoField {
+        ty: String,
+        field: TokenData<'s>,
+    },
+    // a tuple index past the last element
+    NoElement {
+        ty: String,
+        index: usize,
+        span: SrcSpan,
+    },
+    // `step 0` would never reach the end
+    ZeroStep(SrcSpan),
+    // `val x: u8 = 256`, the value would be truncated
+    IntOutOfRange {
+        value: i64,
+        ty: String,
+        span: SrcSpan,
+    },
+    // integer division by a literal zero always aborts
+    DivByZero(SrcSpan),
+    // arrays live on the stack of the call that built them
+    ReturnsArray {
+        func: TokenData<'s>,
+        ty: String,
+    },
+    // nothing decided the type before its field was used
+    UnknownFieldBase {
+        field: String,
+        span: SrcSpan,
+    },
+}
+
+impl<'s> ToDiagnostic for AstTypeErr<'s> {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            AstTypeErr::Mismatch {
+                expected,
+                found,
+                span,
+            } => Diagnostic::error("E0301", String::from("mismatched types"))
+                .with_primary(*span, format!("expected `{}`, found `{}`", expected, found)),
+            AstTypeErr::ReturnMismatch {
+                func,
+                expected,
+                found,
+                span,
+            } => {
+                let msg = format!("mismatched return type in `{}`", func.span);
+                Diagnostic::error("E0302", msg)
+                    .with_primary(*span, format!("expected `{}`, found `{}`", expected, found))
+                    .with_secondary(
+                        func.src_span(),
+                        format!("`{}` returns `{}`", func.span, expected),
+                    )
+            }
+            AstTypeErr::InvalidOperand { op, ty, span } => {
+                let msg = format!("cannot apply `{}` to `{}`", op, ty);
+                Diagnostic::error("E0303", msg)
+                    .with_primary(*span, format!("this has type `{}`", ty))
+            }
+            AstTypeErr::ArgCount {
+                expected,
+                found,
+                variadic,
+                span,
+            } => {
+                let at_least = if *variadic { "at least " } else { "" };
+                let msg = format!(
+                    "function takes {}{} arguments but {} were supplied",
+                    at_least, expected, found
+                );
+                Diagnostic::error("E0304", msg).with_primary(
+                    *span,
+                    format!("expected {}{} arguments", at_least, expected),
+                )
+            }
+            AstTypeErr::NotCallable { ty, span } => {
+                let msg = format!("expected a function, found `{}`", ty);
+                Diagnostic::error("E0305", msg)
+                    .with_primary(*span, String::from("cannot be called"))
+            }
+            AstTypeErr::NoField { ty, field } => {
+                let msg = format!("no field `{}` on type `{}`", field.span, ty);
+                Diagnostic::error("E0306", msg)
+                    .with_primary(field.src_span(), String::from("unknown field"))
+            }
+            AstTypeErr::NoElement { ty, index, span } => {
+                let msg = format!("no field `{}` on type `{}`", index, ty);
+                Diagnostic::error("E0306", msg)
+                    .with_primary(*span, format!("this has no field `{}`", index))
+            }
+            AstTypeErr::ZeroStep(span) => {
+                Diagnostic::error("E0308", String::from("`for` loop with a step of zero"))
+                    .with_primary(*span, String::from("the counter never changes"))
+                    .with_note(String::from("use a negative step to count down"))
+            }
+            AstTypeErr::IntOutOfRange { value, ty, span } => {
+                let msg = format!("integer literal out of range for `{}`", ty);
+                Diagnostic::error("E0309", msg)
+                    .with_primary(*span, format!("`{}` does not fit in `{}`", value, ty))
+            }
+            AstTypeErr::DivByZero(span) => {
+                Diagnostic::error("E0311", String::from("integer division by zero"))
+                    .with_primary(*span, String::from("this divisor is zero"))
+            }
+            AstTypeErr::ReturnsArray { func, ty } => {
+                let msg = format!("cannot return an array from `{}`", func.span);
+                Diagnostic::error("E0310", msg)
+                    .with_primary(func.src_span(), format!("`{}` returns `{}`", func.span, ty))
+                    .with_note(String::from(
+                        "array literals live on the stack of the function that builds them",
+                    ))
+            }
+            AstTypeErr::UnknownFieldBase { field, span } => {
+                let msg = format!("type must be known to access field `{}`", field);
+                Diagnostic::error("E0307", msg)
+                    .with_primary(*span, String::from("type of this is not known here"))
+                    .with_note(String::from(
+                        "add a type annotation where the value is declared",
+                    ))
+            }
+        }
+    }
+}
+
+pub type TypeCheckResult<'s> = Result<TypeTable, Vec<AstTypeErr<'s>>>;
+
+impl<'a, 's> ModulePass<'s> for AstTypeChecker<'a, 's> {
+    type Output = TypeCheckResult<'s>;
+
+    fn run_pass(mut self, m: &Module<'s>) -> Self::Output {
+        self.enums = EnumTable::new(m);
+
+        // signatures first, functions can be called before their definition
+        for item in &m.body {
+            match &item.kind {
+                ItemKind::Func(Function { proto, .. }) => {
+                    let sig = self.proto_type(proto, true);
+                    self.record_sym(&item.nid, sig.clone());
+                    self.record(item.nid, sig);
+                }
+                // there is no body to infer from, C functions default to f64
+                ItemKind::Extern(proto) => {
+                    let sig = self.proto_type(proto, false);
+                    self.record_sym(&item.nid, sig.clone());
+                    self.record(item.nid, sig);
+                }
+                ItemKind::Struct(def) => {
+                    let fields = def
+                        .fields
+                        .iter()
+                        .map(|field| (String::from(field.name.span), field.ty.clone()))
+                        .collect();
+                    self.structs.insert(String::from(def.name.span), fields);
+                }
+                // variants are constants, or functions building the enum from a payload
+                ItemKind::Enum(def) => {
+                    let enum_ty = Type::named(def.name.span);
+                    for variant in &def.variants {
+                        let ty = if variant.payload.is_empty() {
+                            enum_ty.clone()
+                        } else {
+                            Type::func(variant.payload.clone(), enum_ty.clone())
+                        };
+                        self.record_sym(&variant.nid, ty);
+                    }
+                }
+                ItemKind::Error => (),
+            }
+        }
+
+        for item in &m.body {
+            if let ItemKind::Func(f) = &item.kind {
+                self.check_function(&item.nid, f);
+            }
+        }
+
+        self.solve();
+
+        if !self.errs.is_empty() {
+            return Err(self.errs);
+        }
+
+        self.apply_defaults();
+
+        self.check_int_lits();
+        self.check_zero_divisors();
+        self.check_returns(m);
+        if !self.errs.is_empty() {
+            return Err(self.errs);
+        }
+
+        let mut table = TypeTable::new();
+        for (nid, ty) in &self.nodes {
+            table.insert(*nid, self.unifier.zonk_or_default(ty));
+        }
+        Ok(table)
+    }
+}
+
+// `0`, `0.0` or `-0`, a step that is zero whatever its type
+fn is_zero_lit(expr: &Expr) -> bool {
+    match &*expr.kind {
+        ExprKind::Lit(Lit::Int(0)) => true,
+        ExprKind::Lit(Lit::Float(f)) => *f == 0.0,
+        ExprKind::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => is_zero_lit(operand),
+        _ => false,
+    }
+}
+
+// the expression producing the value of a block
+fn tail_span(expr: &Expr) -> SrcSpan {
+    match &*expr.kind {
+        ExprKind::Block(block) => match block.last() {
+            Some(last) => tail_span(last),
+            None => expr.span,
+        },
+        _ => expr.span,
+    }
+}
+
+impl<'a, 's> AstTypeChecker<'a, 's> {
+    pub fn new(mst: &'a ModSymTable) -> Self {
+        Self {
+            mst,
+            sym_types: HashMap::new(),
+            nodes: Vec::new(),
+            constraints: Vec::new(),
+            defaults: Vec::new(),
+            int_lits: Vec::new(),
+            zero_divisors: Vec::new(),
+            unifier: Unifier::new(),
+            errs: Vec::new(),
+            func_ret: None,
+            loops: Vec::new(),
+            structs: HashMap::new(),
+            enums: EnumTable::default(),
+        }
+    }
+
+    fn record_sym(&mut self, nid: &NodeId, ty: Type) {
+        // unresolved nodes were already reported by name resolution
+        if let Some(sid) = self.mst.lookup(nid) {
+            self.sym_types.insert(*sid, ty);
+        }
+    }
+
+    fn record(&mut self, nid: NodeId, ty: Type) -> Type {
+        self.nodes.push((nid, ty.clone()));
+        ty
+    }
+
+    fn equal(&mut self, expected: Type, found: Type, span: SrcSpan) {
+        self.constraints.push(Constraint::Equal {
+            expected,
+            found,
+            span,
+        });
+    }
+
+    fn operand(&mut self, op: &'static str, ty: Type, bound: Bound, span: SrcSpan) {
+        self.constraints.push(Constraint::Operand {
+            op,
+            ty,
+            bound,
+            span,
+        });
+    }
+
+    // unannotated arguments and return values are inferred or f64
+    fn proto_type(&mut self, proto: &FuncProto, infer: bool) -> Type {
+        let mut args = Vec::with_capacity(proto.args.len());
+        for arg in &proto.args {
+            let ty = match &arg.ty {
+                Some(ty) => ty.clone(),
+                None => self.unannotated(infer),
+            };
+            self.record_sym(&arg.nid, ty.clone());
+            args.push(self.record(arg.nid, ty));
+        }
+
+        let ret = match &proto.ret {
+            Some(ty) => ty.clone(),
+            None => self.unannotated(infer),
+        };
+
+        if proto.variadic {
+            Type::variadic_func(args, ret)
+        } else {
+            Type::func(args, ret)
+        }
+    }
+
+    fn unannotated(&mut self, infer: bool) -> Type {
+        if infer {
+            self.unifier.fresh()
+        } else {
+            Type::default_value()
+        }
+    }
+
+    fn check_function(&mut self, nid: &NodeId, f: &Function<'s>) {
+        let sig = self.mst.lookup(nid).and_then(|sid| self.sym_types.get(sid));
+        let ret = match sig.map(Type::kind) {
+            Some(TypeKind::Func { ret, .. }) => ret.clone(),
+            _ => return,
+        };
+
+        self.func_ret = Some((f.proto.name, ret.clone()));
+        let found = self.infer_expr(&f.body);
+        self.func_ret = None;
+
+        self.constraints.push(Constraint::Return {
+            func: f.proto.name,
+            expected: ret,
+            found,
+            span: tail_span(&f.body),
+        });
+    }
+
+    fn infer_expr(&mut self, expr: &Expr<'s>) -> Type {
+        let ty = match &*expr.kind {
+            ExprKind::Lit(lit) => self.lit_type(lit, expr.span),
+            ExprKind::Var(_) => {
+                let sym_ty = self
+                    .mst
+                    .lookup(&expr.nid)
+                    .and_then(|sid| self.sym_types.get(sid));
+                match sym_ty {
+                    Some(ty) => ty.clone(),
+                    None => self.unifier.fresh(),
+                }
+            }
+            ExprKind::Binary { op, lhs, rhs } => self.binary_type(*op, lhs, rhs),
+            ExprKind::Unary { op, operand } => {
+                let ty = self.unary_type(*op, operand);
+                // `-128` is checked as one literal, it fits in an `i8`
+                if let (UnaryOp::Neg, ExprKind::Lit(Lit::Int(int))) = (op, &*operand.kind) {
+                    if let Some(lit) = self.int_lits.last_mut() {
+                        lit.1 = -int;
+                        lit.2 = expr.span;
+                    }
+                }
+                ty
+            }
+            ExprKind::Call { callee, args } => self.call_type(callee, args),
+            ExprKind::StructLit { name, fields } => {
+                for init in fields {
+                    let value_ty = self.infer_expr(&init.value);
+                    // unknown fields were reported by name resolution
+                    if let Some(field_ty) = self.field_type(name.span, init.name.span) {
+                        self.equal(field_ty, value_ty, init.value.span);
+                    }
+                }
+                Type::named(name.span)
+            }
+            ExprKind::ArrayLit(elems) => {
+                let elem_ty = self.unifier.fresh();
+                for elem in elems {
+                    let found = self.infer_expr(elem);
+                    self.equal(elem_ty.clone(), found, elem.span);
+                }
+                Type::array(elem_ty)
+            }
+            ExprKind::Index { base, index } => {
+                let base_ty = self.infer_expr(base);
+                let elem_ty = self.unifier.fresh();
+                self.equal(Type::array(elem_ty.clone()), base_ty, base.span);
+
+                let index_ty = self.infer_expr(index);
+                self.operand("[]", index_ty, Bound::Integer, index.span);
+                elem_ty
+            }
+            ExprKind::Field { base, field } => {
+                let base_ty = self.infer_expr(base);
+                let found = self.unifier.fresh();
+                self.constraints.push(Constraint::Field {
+                    base: base_ty,
+                    field: *field,
+                    found: found.clone(),
+                    span: base.span,
+                });
+                found
+            }
+            ExprKind::TupleLit(elems) => {
+                let elem_tys = elems.iter().map(|elem| self.infer_expr(elem)).collect();
+                Type::tuple(elem_tys)
+            }
+            ExprKind::TupleField { base, index } => {
+                let base_ty = self.infer_expr(base);
+                let found = self.unifier.fresh();
+                self.constraints.push(Constraint::Element {
+                    base: base_ty,
+                    index: *index,
+                    found: found.clone(),
+                    span: base.span,
+                });
+                found
+            }
+            ExprKind::If {
+                cond,
+                if_body,
+                else_body,
+            } => {
+                let cond_ty = self.infer_expr(cond);
+                self.equal(Type::bool(), cond_ty, cond.span);
+
+                let if_ty = self.infer_expr(if_body);
+                let else_ty = self.infer_expr(else_body);
+                self.equal(if_ty.clone(), else_ty, tail_span(else_body));
+                if_ty
+            }
+            ExprKind::Block(block) => {
+                let mut last = Type::nil();
+                for sub in block {
+                    last = self.infer_expr(sub);
+                }
+                last
+            }
+            ExprKind::Decl(vd) => {
+                let value_ty = self.infer_expr(&vd.value);
+                let bound_ty = match &vd.ty {
+                    Some(ty) => {
+                        self.equal(ty.clone(), value_ty, vd.value.span);
+                        ty.clone()
+                    }
+                    None => value_ty,
+                };
+                self.record_sym(&expr.nid, bound_ty);
+                Type::nil()
+            }
+            ExprKind::Destructure { pat, ty, value, .. } => {
+                let value_ty = self.infer_expr(value);
+                let bound_ty = match ty {
+                    Some(ty) => {
+                        self.equal(ty.clone(), value_ty, value.span);
+                        ty.clone()
+                    }
+                    None => value_ty,
+                };
+                self.pattern_type(pat, bound_ty);
+                Type::nil()
+            }
+            ExprKind::Let { bound, let_body } => {
+                for decl in bound {
+                    self.infer_expr(decl);
+                }
+                self.infer_expr(let_body)
+            }
+            ExprKind::While { cond, while_body } => {
+                let cond_ty = self.infer_expr(cond);
+                self.equal(Type::bool(), cond_ty, cond.span);
+                self.infer_loop_body(None, while_body);
+                Type::nil()
+            }
+            ExprKind::Loop(loop_body) => {
+                let ty = self.unifier.fresh();
+                self.infer_loop_body(Some(ty.clone()), loop_body);
+                ty
+            }
+            ExprKind::For { range, for_body } => {
+                let var_ty = self.infer_expr(&range.start);
+                self.operand("..", var_ty.clone(), Bound::Numeric, range.start.span);
+                let end_ty = self.infer_expr(&range.end);
+                self.equal(var_ty.clone(), end_ty, range.end.span);
+                if let Some(step) = &range.step {
+                    let step_ty = self.infer_expr(step);
+                    self.equal(var_ty.clone(), step_ty, step.span);
+                    if is_zero_lit(step) {
+                        self.errs.push(AstTypeErr::ZeroStep(step.span));
+                    }
+                }
+
+                self.record_sym(&range.var_nid, var_ty);
+                self.infer_loop_body(None, for_body);
+                Type::nil()
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                let scrutinee_ty = self.infer_expr(scrutinee);
+                let ty = self.unifier.fresh();
+                for arm in arms {
+                    self.pattern_type(&arm.pat, scrutinee_ty.clone());
+                    let arm_ty = self.infer_expr(&arm.body);
+                    self.equal(ty.clone(), arm_ty, tail_span(&arm.body));
+                }
+                ty
+            }
+            // jumps never produce a value, so they fit any type
+            ExprKind::Break(value) => {
+                let value_ty = value.as_ref().map(|v| (self.infer_expr(v), v.span));
+                // a bare `break` leaves a `loop` with nil
+                let (found, span) = value_ty.unwrap_or((Type::nil(), expr.span));
+                if let Some(Some(loop_ty)) = self.loops.last().cloned() {
+                    self.equal(loop_ty, found, span);
+                }
+                self.unifier.fresh()
+            }
+            ExprKind::Continue => self.unifier.fresh(),
+            ExprKind::Return(value) => {
+                let found = self.infer_expr(value);
+                if let Some((func, expected)) = self.func_ret.clone() {
+                    self.constraints.push(Constraint::Return {
+                        func,
+                        expected,
+                        found,
+                        span: value.span,
+                    });
+                }
+                self.unifier.fresh()
+            }
+            ExprKind::Error => self.unifier.fresh(),
+        };
+
+        self.record(expr.nid, ty)
+    }
+
+    // patterns take the type of the matched value
+    fn pattern_type(&mut self, pat: &Pattern<'s>, ty: Type) {
+        match &pat.kind {
+            PatternKind::Wildcard => (),
+            PatternKind::Lit(lit) => {
+                let lit_ty = self.lit_type(lit, pat.span);
+                self.equal(ty, lit_ty, pat.span);
+            }
+            PatternKind::Bind(name) => match self.enums.variant(name.span) {
+                Some(variant) => {
+                    let enum_ty = Type::named(&variant.enum_name);
+                    self.equal(ty, enum_ty, pat.span);
+                }
+                None => self.record_sym(&pat.nid, ty),
+            },
+            PatternKind::Variant { name, args } => {
+                // unknown variants were reported by name resolution
+                let variant = match self.enums.variant(name.span) {
+                    Some(variant) => variant.clone(),
+                    None => return,
+                };
+                self.equal(ty, Type::named(&variant.enum_name), pat.span);
+                for (arg, arg_ty) in args.iter().zip(variant.payload) {
+                    self.pattern_type(arg, arg_ty);
+                }
+            }
+            PatternKind::Tuple(elems) => {
+                let elem_tys: Vec<_> = elems.iter().map(|_| self.unifier.fresh()).collect();
+                self.equal(ty, Type::tuple(elem_tys.clone()), pat.span);
+                for (elem, elem_ty) in elems.iter().zip(elem_tys) {
+                    self.pattern_type(elem, elem_ty);
+                }
+            }
+        }
+    }
+
+    fn field_type(&self, ty: &str, field: &str) -> Option<Type> {
+        let fields = self.structs.get(ty)?;
+        let (_, field_ty) = fields.iter().find(|(name, _)| name == field)?;
+        Some(field_ty.clone())
+    }
+
+    fn infer_loop_body(&mut self, loop_ty: Option<Type>, body: &Expr<'s>) {
+        self.loops.push(loop_ty);
+        self.infer_expr(body);
+        self.loops.pop();
+    }
+
+    fn lit_type(&mut self, lit: &Lit, span: SrcSpan) -> Type {
+        match lit {
+            Lit::Int(int) => {
+                let ty = self.unifier.fresh_bounded(Bound::Numeric);
+                let default = Type::int(IntWidth::Int64);
+                self.defaults.push((ty.clone(), default));
+                self.int_lits.push((ty.clone(), *int, span));
+                ty
+            }
+            Lit::Float(_) => {
+                let ty = self.unifier.fresh_bounded(Bound::Float);
+                let default = Type::float(FloatWidth::Float64);
+                self.defaults.push((ty.clone(), default));
+                ty
+            }
+            Lit::Bool(_) => Type::bool(),
+            Lit::Char(_) => Type::char(),
+            Lit::Str(_) => Type::str(),
+        }
+    }
+
+    fn binary_type(&mut self, op: BinaryOp, lhs: &Expr<'s>, rhs: &Expr<'s>) -> Type {
+        let lhs_ty = self.infer_expr(lhs);
+        let rhs_ty = self.infer_expr(rhs);
+
+        match op {
+            BinaryOp::Assign => {
+                self.equal(lhs_ty.clone(), rhs_ty, rhs.span);
+                lhs_ty
+            }
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                self.operand(op.symbol(), lhs_ty.clone(), Bound::Numeric, lhs.span);
+                if matches!(op, BinaryOp::Div | BinaryOp::Rem) && is_zero_lit(rhs) {
+                    self.zero_divisors.push((lhs_ty.clone(), rhs.span));
+                }
+                self.equal(lhs_ty.clone(), rhs_ty, rhs.span);
+                lhs_ty
+            }
+            // shift amounts have the type of the shifted value, like LLVM
+            BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::Shl
+            | BinaryOp::Shr => {
+                self.operand(op.symbol(), lhs_ty.clone(), Bound::Integer, lhs.span);
+                self.equal(lhs_ty.clone(), rhs_ty, rhs.span);
+                lhs_ty
+            }
+            BinaryOp::Lt | BinaryOp::LtEt | BinaryOp::Gt | BinaryOp::GtEt => {
+                self.operand(op.symbol(), lhs_ty.clone(), Bound::Ordered, lhs.span);
+                self.equal(lhs_ty, rhs_ty, rhs.span);
+                Type::bool()
+            }
+            BinaryOp::Et | BinaryOp::NotEt => {
+                self.operand(op.symbol(), lhs_ty.clone(), Bound::Equatable, lhs.span);
+                self.equal(lhs_ty, rhs_ty, rhs.span);
+                Type::bool()
+            }
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr => {
+                self.equal(Type::bool(), lhs_ty, lhs.span);
+                self.equal(Type::bool(), rhs_ty, rhs.span);
+                Type::bool()
+            }
+        }
+    }
+
+    fn unary_type(&mut self, op: UnaryOp, operand: &Expr<'s>) -> Type {
+        let ty = self.infer_expr(operand);
+
+        match op {
+            UnaryOp::Neg => {
+                self.operand(op.symbol(), ty.clone(), Bound::Numeric, operand.span);
+                ty
+            }
+            UnaryOp::LogicalNot => {
+                self.equal(Type::bool(), ty, operand.span);
+                Type::bool()
+            }
+        }
+    }
+
+    fn call_type(&mut self, callee: &Expr<'s>, args: &[Expr<'s>]) -> Type {
+        let callee_ty = match self.builtin_of(callee) {
+            Some(builtin) => {
+                let ty = self.builtin_type(builtin);
+                self.record(callee.nid, ty)
+            }
+            None => self.infer_expr(callee),
+        };
+        let args = args
+            .iter()
+            .map(|arg| (self.infer_expr(arg), arg.span))
+            .collect();
+
+        let ret = self.unifier.fresh();
+        self.constraints.push(Constraint::Call {
+            callee: callee_ty,
+            args,
+            ret: ret.clone(),
+            span: callee.span,
+        });
+        ret
+    }
+
+    // name resolution leaves builtins without a symbol
+    fn builtin_of(&self, callee: &Expr<'s>) -> Option<Builtin> {
+        match &*callee.kind {
+            ExprKind::Var(td) if self.mst.lookup(&callee.nid).is_none() => {
+                Builtin::from_name(td.span)
+            }
+            _ => None,
+        }
+    }
+
+    fn builtin_type(&mut self, builtin: Builtin) -> Type {
+        match builtin {
+            Builtin::Len => {
+                let arr = Type::array(self.unifier.fresh());
+                Type::func(vec![arr], Type::int(IntWidth::Int64))
+            }
+        }
+    }
+
+    fn solve(&mut self) {
+        let constraints = std::mem::take(&mut self.constraints);
+        // fields of a value whose type is not known yet
+        let mut deferred = Vec::new();
+        for constraint in constraints {
+            match constraint {
+                Constraint::Equal {
+                    expected,
+                    found,
+                    span,
+                } => self.unify_or_report(&expected, &found, span),
+                Constraint::Return {
+                    func,
+                    expected,
+                    found,
+                    span,
+                } => {
+                    if self.unifier.unify(&expected, &found).is_err() {
+                        self.errs.push(AstTypeErr::ReturnMismatch {
+                            func,
+                            expected: self.unifier.describe(&expected),
+                            found: self.unifier.describe(&found),
+                            span,
+                        });
+                    }
+                }
+                Constraint::Operand {
+                    op,
+                    ty,
+                    bound,
+                    span,
+                } => {
+                    if self.unifier.require(&ty, bound).is_err() {
+                        self.errs.push(AstTypeErr::InvalidOperand {
+                            op,
+                            ty: self.unifier.describe(&ty),
+                            span,
+                        });
+                    }
+                }
+                Constraint::Call {
+                    callee,
+                    args,
+                    ret,
+                    span,
+                } => self.solve_call(&callee, &args, &ret, span),
+                Constraint::Field { ref base, .. } | Constraint::Element { ref base, .. }
+                    if matches!(self.unifier.resolve(base).kind(), TypeKind::Var(_)) =>
+                {
+                    deferred.push(constraint);
+                }
+                Constraint::Field { .. } | Constraint::Element { .. } => {
+                    self.solve_member(constraint)
+                }
+            }
+        }
+
+        // the rest of the module may have decided them by now
+        for constraint in deferred {
+            self.solve_member(constraint);
+        }
+    }
+
+    fn solve_member(&mut self, constraint: Constraint<'s>) {
+        match constraint {
+            Constraint::Field {
+                base,
+                field,
+                found,
+                span,
+            } => self.solve_field(&base, field, &found, span),
+            Constraint::Element {
+                base,
+                index,
+                found,
+                span,
+            } => self.solve_element(&base, index, &found, span),
+            _ => unreachable!("only fields wait for the type of their base"),
+        }
+    }
+
+    fn solve_element(&mut self, base: &Type, index: usize, found: &Type, span: SrcSpan) {
+        let base = self.unifier.resolve(base);
+        match base.kind() {
+            TypeKind::Tuple(elems) if index < elems.len() => {
+                self.unify_or_report(&elems[index], found, span)
+            }
+            TypeKind::Var(_) => self.errs.push(AstTypeErr::UnknownFieldBase {
+                field: index.to_string(),
+                span,
+            }),
+            _ => self.errs.push(AstTypeErr::NoElement {
+                ty: self.unifier.describe(&base),
+                index,
+                span,
+            }),
+        }
+    }
+
+    fn solve_field(&mut self, base: &Type, field: TokenData<'s>, found: &Type, span: SrcSpan) {
+        let base = self.unifier.resolve(base);
+        match base.kind() {
+            TypeKind::Named(name) => match self.field_type(name, field.span) {
+                Some(field_ty) => self.unify_or_report(&field_ty, found, field.src_span()),
+                None => self.errs.push(AstTypeErr::NoField {
+                    ty: self.unifier.describe(&base),
+                    field,
+                }),
+            },
+            TypeKind::Var(_) => self.errs.push(AstTypeErr::UnknownFieldBase {
+                field: String::from(field.span),
+                span,
+            }),
+            _ => self.errs.push(AstTypeErr::NoField {
+                ty: self.unifier.describe(&base),
+                field,
+            }),
+        }
+    }
+
+    fn solve_call(&mut self, callee: &Type, args: &[(Type, SrcSpan)], ret: &Type, span: SrcSpan) {
+        let callee = self.unifier.resolve(callee);
+        match callee.kind() {
+            TypeKind::Func {
+                args: params,
+                ret: func_ret,
+                variadic,
+            } => {
+                let too_few = args.len() < params.len();
+                if too_few || (!variadic && args.len() != params.len()) {
+                    self.errs.push(AstTypeErr::ArgCount {
+                        expected: params.len(),
+                        found: args.len(),
+                        variadic: *variadic,
+                        span,
+                    });
+                    return;
+                }
+
+                // extra arguments of variadic functions keep their own type
+                for (param, (arg, arg_span)) in params.iter().zip(args.iter()) {
+                    self.unify_or_report(param, arg, *arg_span);
+                }
+                self.unify_or_report(func_ret, ret, span);
+            }
+            // a callee of unknown type must be a function
+            TypeKind::Var(_) => {
+                let arg_types = args.iter().map(|(arg, _)| arg.clone()).collect();
+                self.unify_or_report(&callee, &Type::func(arg_types, ret.clone()), span);
+            }
+            _ => self.errs.push(AstTypeErr::NotCallable {
+                ty: self.unifier.describe(&callee),
+                span,
+            }),
+        }
+    }
+
+    fn unify_or_report(&mut self, expected: &Type, found: &Type, span: SrcSpan) {
+        if self.unifier.unify(expected, found).is_err() {
+            self.errs.push(AstTypeErr::Mismatch {
+                expected: self.unifier.describe(expected),
+                found: self.unifier.describe(found),
+                span,
+            });
+        }
+    }
+
+    fn check_zero_divisors(&mut self) {
+        for (ty, span) in std::mem::take(&mut self.zero_divisors) {
+            let ty = self.unifier.zonk_or_default(&ty);
+            // a float divided by zero is an infinity
+            if let TypeKind::Int(_) | TypeKind::UInt(_) = ty.kind() {
+                self.errs.push(AstTypeErr::DivByZero(span));
+            }
+        }
+    }
+
+    fn check_int_lits(&mut self) {
+        for (ty, value, span) in std::mem::take(&mut self.int_lits) {
+            let ty = self.unifier.zonk_or_default(&ty);
+            let (min, max) = match ty.kind() {
+                TypeKind::Int(width) => {
+                    let half = 1i128 << (width.bits() - 1);
+                    (-half, half - 1)
+                }
+                TypeKind::UInt(width) => (0, (1i128 << width.bits()) - 1),
+                // an integer literal of a float type is exact enough
+                _ => continue,
+            };
+            if !(min..=max).contains(&(value as i128)) {
+                self.errs.push(AstTypeErr::IntOutOfRange {
+                    value,
+                    ty: ty.to_string(),
+                    span,
+                });
+            }
+        }
+    }
+
+    fn check_returns(&mut self, m: &Module<'s>) {
+        for item in &m.body {
+            let name = match &item.kind {
+                ItemKind::Func(f) => f.proto.name,
+                _ => continue,
+            };
+            let sig = self
+                .mst
+                .lookup(&item.nid)
+                .and_then(|sid| self.sym_types.get(sid));
+            let ret = match sig.map(|sig| self.unifier.zonk_or_default(sig)) {
+                Some(sig) => match sig.kind() {
+                    TypeKind::Func { ret, .. } => ret.clone(),
+                    _ => continue,
+                },
+                None => continue,
+            };
+            if self.holds_array(&ret) {
+                self.errs.push(AstTypeErr::ReturnsArray {
+                    func: name,
+                    ty: ret.to_string(),
+                });
+            }
+        }
+    }
+
+    // structs and enums cannot contain themselves, so this ends
+    fn holds_array(&self, ty: &Type) -> bool {
+        match ty.kind() {
+            TypeKind::Array(_) => true,
+            TypeKind::Tuple(elems) => elems.iter().any(|elem| self.holds_array(elem)),
+            TypeKind::Named(name) => match self.structs.get(name) {
+                Some(fields) => fields.iter().any(|(_, ty)| self.holds_array(ty)),
+                None => self
+                    .enums
+                    .variants(name)
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|v| &v.payload)
+                    .any(|ty| self.holds_array(ty)),
+            },
+            _ => false,
+        }
+    }
+
+    fn apply_defaults(&mut self) {
+        // float literals first, `1 + 2.5` is a float
+        let mut defaults = std::mem::take(&mut self.defaults);
+        defaults.sort_by_key(|(_, default)| !default.is_float());
+
+        for (ty, default) in defaults {
+            // a variable still unknown accepts its own default
+            let _ = self.unifier.unify(&ty, &default);
+        }
+    }
+}
+
+// substitution built up while solving constraints
+struct Unifier {
+    // what each variable was unified with
+    subst: Vec<Option<Type>>,
+    // what each unknown variable must accept later
+    bounds: Vec<Option<Bound>>,
+}
+
+impl Unifier {
+    fn new() -> Self {
+        Self {
+            subst: Vec::new(),
+            bounds: Vec::new(),
+        }
+    }
+
+    fn fresh(&mut self) -> Type {
+        let var = TypeVar::new(self.subst.len() as u32);
+        self.subst.push(None);
+        self.bounds.push(None);
+        Type::var(var)
+    }
+
+    fn fresh_bounded(&mut self, bound: Bound) -> Type {
+        let ty = self.fresh();
+        if let TypeKind::Var(var) = ty.kind() {
+            self.bounds[var.index()] = Some(bound);
+        }
+        ty
+    }
+
+    // follows variables until a known type or an unknown variable
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty.kind() {
+            TypeKind::Var(var) => match &self.subst[var.index()] {
+                Some(bound_ty) => self.resolve(bound_ty),
+                None => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> Result<(), ()> {
+        let (a, b) = (self.resolve(a), self.resolve(b));
+        match (a.kind(), b.kind()) {
+            (TypeKind::Var(x), TypeKind::Var(y)) if x == y => Ok(()),
+            (TypeKind::Var(x), _) => self.bind(*x, &b),
+            (_, TypeKind::Var(y)) => self.bind(*y, &a),
+            (
+                TypeKind::Func {
+                    args: a_args,
+                    ret: a_ret,
+                    variadic: a_variadic,
+                },
+                TypeKind::Func {
+                    args: b_args,
+                    ret: b_ret,
+                    variadic: b_variadic,
+                },
+            ) => {
+                if a_args.len() != b_args.len() || a_variadic != b_variadic {
+                    return Err(());
+                }
+                for (a_arg, b_arg) in a_args.iter().zip(b_args.iter()) {
+                    self.unify(a_arg, b_arg)?;
+                }
+                self.unify(a_ret, b_ret)
+            }
+            (TypeKind::Array(a_elem), TypeKind::Array(b_elem)) => self.unify(a_elem, b_elem),
+            (TypeKind::Tuple(a_elems), TypeKind::Tuple(b_elems)) => {
+                if a_elems.len() != b_elems.len() {
+                    return Err(());
+                }
+                for (a_elem, b_elem) in a_elems.iter().zip(b_elems.iter()) {
+                    self.unify(a_elem, b_elem)?;
+                }
+                Ok(())
+            }
+            _ if a == b => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    fn bind(&mut self, var: TypeVar, ty: &Type) -> Result<(), ()> {
+        if self.occurs(var, ty) {
+            return Err(());
+        }
+        if let Some(bound) = self.bounds[var.index()] {
+            self.require(ty, bound)?;
+        }
+        self.subst[var.index()] = Some(ty.clone());
+        Ok(())
+    }
+
+    fn require(&mut self, ty: &Type, bound: Bound) -> Result<(), ()> {
+        let ty = self.resolve(ty);
+        match ty.kind() {
+            // keep the stricter bound
+            TypeKind::Var(var) => {
+                let merged = match self.bounds[var.index()] {
+                    Some(prev) => prev.meet(bound).ok_or(())?,
+                    None => bound,
+                };
+                self.bounds[var.index()] = Some(merged);
+                Ok(())
+            }
+            _ if bound.accepts(&ty) => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    fn occurs(&self, var: TypeVar, ty: &Type) -> bool {
+        let ty = self.resolve(ty);
+        match ty.kind() {
+            TypeKind::Var(other) => *other == var,
+            TypeKind::Func { args, ret, .. } => {
+                args.iter().any(|arg| self.occurs(var, arg)) || self.occurs(var, ret)
+            }
+            TypeKind::Array(elem) => self.occurs(var, elem),
+            TypeKind::Tuple(elems) => elems.iter().any(|elem| self.occurs(var, elem)),
+            _ => false,
+        }
+    }
+
+    // fully substituted, nothing decided the remaining variables
+    // f64 accepts every bound but Integer
+    fn zonk_or_default(&self, ty: &Type) -> Type {
+        let ty = self.resolve(ty);
+        match ty.kind() {
+            TypeKind::Var(var) => match self.bounds[var.index()] {
+                Some(Bound::Integer) => Type::int(IntWidth::Int64),
+                _ => Type::default_value(),
+            },
+            TypeKind::Func {
+                args,
+                ret,
+                variadic,
+            } => Type::new(TypeKind::Func {
+                args: args.iter().map(|arg| self.zonk_or_default(arg)).collect(),
+                ret: self.zonk_or_default(ret),
+                variadic: *variadic,
+            }),
+            TypeKind::Array(elem) => Type::array(self.zonk_or_default(elem)),
+            TypeKind::Tuple(elems) => Type::tuple(
+                elems
+                    .iter()
+                    .map(|elem| self.zonk_or_default(elem))
+                    .collect(),
+            ),
+            _ => ty,
+        }
+    }
+
+    // for diagnostics, unknown types show what they must accept
+    fn describe(&self, ty: &Type) -> String {
+        let ty = self.resolve(ty);
+        match ty.kind() {
+            TypeKind::Var(var) => match self.bounds[var.index()] {
+                Some(Bound::Float) => String::from("{float}"),
+                Some(Bound::Numeric) => String::from("{number}"),
+                Some(Bound::Integer) => String::from("{integer}"),
+                Some(Bound::Ordered) => String::from("{number or char}"),
+                Some(Bound::Equatable) | None => String::from("_"),
+            },
+            TypeKind::Func {
+                args,
+                ret,
+                variadic,
+            } => {
+                let mut args: Vec<_> = args.iter().map(|arg| self.describe(arg)).collect();
+                if *variadic {
+                    args.push(String::from("..."));
+                }
+                format!("fun({}): {}", args.join(", "), self.describe(ret))
+            }
+            TypeKind::Array(elem) => format!("[{}]", self.describe(elem)),
+            TypeKind::Tuple(elems) => {
+                let elems: Vec<_> = elems.iter().map(|elem| self.describe(elem)).collect();
+                format!("({})", elems.join(", "))
+            }
+            _ => ty.to_string(),
+        }
+    }
+}
+
+#[test]
+fn test_infers_unannotated_signatures() {
+    use crate::cli::check::expect_checked;
+
+    let src = "mod m
+fun count(n)
+    val mut i = 0
+    while i < n
+        i = i + 1
+    end
+    i
+end
+fun half(x)
+    x / 2
+end
+fun main()
+    half(1.5)
+end
+";
+    let checked = expect_checked(src);
+
+    let sigs: Vec<_> = checked
+        .module
+        .body
+        .iter()
+        .map(|item| checked.types.lookup(&item.nid).unwrap().to_string())
+        .collect();
+    assert_eq!(sigs, vec!["fun(i64): i64", "fun(f64): f64", "fun(): f64"]);
+}
+
+#[test]
+fn test_mismatch_at_call_site() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun inc(x)\n    x + 1\nend\nfun main()\n    inc(true)\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0301");
+    assert_eq!(diags[0].labels[0].msg, "expected `{number}`, found `bool`");
+}
+
+#[test]
+fn test_negating_a_bool() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun main()\n    val ok = !(1 < 2)\n    -ok\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0303");
+    assert_eq!(diags[0].msg, "cannot apply `-` to `bool`");
+}
+
+#[test]
+fn test_comparisons_and_logical_ops() {
+    use crate::cli::check::{expect_checked, expect_errors};
+
+    let src = "mod m\nfun odd_or_past(x, lo, hi)\n    x >= lo && x % 2 != 0 || x > hi\nend\n";
+    let checked = expect_checked(src);
+    let sig = checked.types.lookup(&checked.module.body[0].nid).unwrap();
+    assert_eq!(sig.to_string(), "fun(i64, i64, i64): bool");
+
+    // both sides of a short-circuit op must be `bool`
+    let src = "mod m\nfun f(ok: bool)\n    ok || 1\nend\n";
+    let diags = expect_errors(src);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0301");
+    assert_eq!(diags[0].labels[0].msg, "expected `bool`, found `{number}`");
+}
+
+#[test]
+fn test_statement_value_is_nil() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun f(n: i64): i64\n    val mut i = 0\n    while i < n\n        i = i + 1\n    end\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0302");
+    assert_eq!(diags[0].msg, "mismatched return type in `f`");
+}
+
+#[test]
+fn test_user_op_is_a_call() {
+    use crate::cli::check::expect_checked;
+
+    let src =
+        "mod m\nfun binary|> 5 (x, f)\n    x * 10 + f\nend\nfun main()\n    1 |> 2 + 3\nend\n";
+    let checked = expect_checked(src);
+
+    let op_ty = checked.types.lookup(&checked.module.body[0].nid).unwrap();
+    assert_eq!(op_ty.to_string(), "fun(i64, i64): i64");
+}
+
+#[test]
+fn test_bitwise_ops_default_to_i64() {
+    use crate::cli::check::{expect_checked, expect_errors};
+
+    let src = "mod m\nfun mix(a, b)\n    a ^ b << 3\nend\nfun bad(x)\n    x & 1.5\nend\n";
+    let diags = expect_errors(src);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(
+        diags[0].labels[0].msg,
+        "expected `{integer}`, found `{float}`"
+    );
+
+    let src = "mod m\nfun mix(a, b)\n    a ^ b << 3\nend\n";
+    let checked = expect_checked(src);
+    let mix_ty = checked.types.lookup(&checked.module.body[0].nid).unwrap();
+    assert_eq!(mix_ty.to_string(), "fun(i64, i64): i64");
+}
+
+#[test]
+fn test_integer_division_by_literal_zero() {
+    use crate::cli::check::{expect_checked, expect_errors};
+
+    let src = "mod m\nfun f(a: i64, b: u8): u8\n    val c = a / 0\n    b % 0\nend\n";
+    let diags = expect_errors(src);
+    assert_eq!(diags.len(), 2);
+    assert!(diags.iter().all(|d| d.code == "E0311"));
+
+    // floats divide to an infinity
+    expect_checked("mod m\nfun f(x: f64): f64\n    x / 0\nend\n");
+}
+
+#[test]
+fn test_field_types() {
+    use crate::cli::check::{expect_checked, expect_errors};
+
+    let src = "mod m
+struct Point x: f64, y: f64 end
+fun norm(p: Point)
+    val mut q = p
+    q.y = 0.0
+    q.x * q.x
+end
+";
+    let checked = expect_checked(src);
+    let norm_ty = checked.types.lookup(&checked.module.body[1].nid).unwrap();
+    assert_eq!(norm_ty.to_string(), "fun(Point): f64");
+
+    let src = "mod m\nstruct Point x: f64, y: f64 end\nfun f(p: Point)\n    p.z\nend\n";
+    let diags = expect_errors(src);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].msg, "no field `z` on type `Point`");
+}
+
+#[test]
+fn test_index_must_be_an_integer() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun f(a: [f64]): f64\n    a[0.5]\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0303");
+    assert_eq!(diags[0].msg, "cannot apply `[]` to `{float}`");
+}
+
+#[test]
+fn test_tuple_index_past_the_end() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun min_max(a: f64, b: f64): (f64, f64)\n    (a, b) if a < b else (b, a)\nend\nfun f(): f64\n    val (lo, hi) = min_max(2.0, 1.0)\n    min_max(lo, hi).2\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0306");
+    assert_eq!(diags[0].msg, "no field `2` on type `(f64, f64)`");
+}
+
+#[test]
+fn test_function_value_signature() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun twice(f: fun(i64): i64, x: i64): i64\n    f(f(x))\nend\nfun half(x: f64): f64\n    x / 2.0\nend\nfun main(): i64\n    twice(half, 8)\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0301");
+    assert_eq!(
+        diags[0].labels[0].msg,
+        "expected `fun(i64): i64`, found `fun(f64): f64`"
+    );
+}
+
+#[test]
+fn test_for_step_counts_down_but_not_by_zero() {
+    use crate::cli::check::{expect_checked, expect_errors};
+
+    let down = "mod m\nfun f(): i64\n    val mut n = 0\n    for i in 10..0 step -1\n        n = n + i\n    end\n    n\nend\n";
+    expect_checked(down);
+
+    let src = "mod m\nfun f(): i64\n    val mut n = 0\n    for i in 0..10 step 0\n        n = n + i\n    end\n    n\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0308");
+}
+
+#[test]
+fn test_int_literal_out_of_range() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nfun f(): u8\n    val small: i8 = -128\n    val x: i8 = 300\n    val y: u8 = -1\n    255\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 2);
+    assert_eq!(diags[0].code, "E0309");
+    assert_eq!(diags[0].msg, "integer literal out of range for `i8`");
+    assert_eq!(diags[1].labels[0].msg, "`-1` does not fit in `u8`");
+}
+
+#[test]
+fn test_array_cannot_be_returned() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m\nstruct V xs: [f64] end\nfun wrap(): V\n    V { xs: [1.0, 2.0] }\nend\n";
+    let diags = expect_errors(src);
+
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0].code, "E0310");
+    assert_eq!(diags[0].labels[0].msg, "`wrap` returns `V`");
+}
@@ -1,8 +1,10 @@
 
 use std::io::stdout;
+use std::process;
 
 use nosh::cli;
 use nosh::cli::build;
+use nosh::cli::check;
 use nosh::cli::repl;
 use nosh::cli::run;
 
@@ -21,16 +23,30 @@ fn main() {
             let opt_str = subm.value_of("optlevel").unwrap().parse().unwrap();
             let opt_level = cli::int_to_opt_level(opt_str);
             let emitter = cli::stdout_emission(subm.value_of("emit"));
-            build::build_file(filename, opt_level, emitter)
+            let lints = cli::lint_options(subm.values_of("warn"));
+            let bounds_checks = !subm.is_present("no-bounds-checks");
+            if !build::build_file(filename, opt_level, emitter, lints, bounds_checks) {
+                process::exit(1);
+            }
         }
         ("repl", Some(_subm)) => repl::begin_repl(),
         ("run", Some(subm)) => {
             let filename = subm.value_of("FILE").unwrap();
             let opt_str = subm.value_of("optlevel").unwrap().parse().unwrap();
             let opt_level = cli::int_to_opt_level(opt_str);
-            run::run_file(filename, opt_level)
+            let lints = cli::lint_options(subm.values_of("warn"));
+            let bounds_checks = !subm.is_present("no-bounds-checks");
+            if !run::run_file(filename, opt_level, lints, bounds_checks) {
+                process::exit(1);
+            }
+        }
+        ("check", Some(subm)) => {
+            let filename = subm.value_of("FILE").unwrap();
+            let lints = cli::lint_options(subm.values_of("warn"));
+            if !check::check_file(filename, lints) {
+                process::exit(1);
+            }
         }
-        ("check", Some(_subm)) => unimplemented!("No Checking yet..."),
         ("shell", Some(subm)) => {
             use clap::Shell;
             use std::str::FromStr;
@@ -1,10 +1,13 @@
 pub mod build;
+pub mod check;
 pub mod repl;
 pub mod run;
 
 use inkwell::OptimizationLevel;
 
-use clap::{App, AppSettings, Arg, SubCommand, Shell};
+use clap::{App, AppSettings, Arg, SubCommand, Shell, Values};
+
+use check::CheckOptions;
 
 pub fn int_to_opt_level(level: u8) -> OptimizationLevel {
     match level {
@@ -33,6 +36,28 @@ pub fn stdout_emission(s: Option<&str>) -> Option<NoshEmit> {
     }
 }
 
+// lints turned on with -W
+pub fn lint_options(warns: Option<Values>) -> CheckOptions {
+    let mut opts = CheckOptions::default();
+    for lint in warns.into_iter().flatten() {
+        if lint == "shadowing" {
+            opts.warn_shadowing = true;
+        }
+    }
+    opts
+}
+
+fn lint_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("warn")
+        .long("warn")
+        .short("W")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .possible_values(&["shadowing"])
+        .help("enable an optional lint")
+}
+
 trait BuildFileCli<'a, 'b> {
     fn append_build_file_args(self) -> Self;
 }
@@ -51,6 +76,12 @@ impl<'a, 'b> BuildFileCli<'a, 'b> for App<'a, 'b> {
                 .default_value("0")
                 .possible_values(&["0", "1", "2", "3"]),
         )
+        .arg(
+            Arg::with_name("no-bounds-checks")
+                .long("no-bounds-checks")
+                .help("index arrays without comparing against their length"),
+        )
+        .arg(lint_arg())
     }
 }
 
@@ -87,7 +118,13 @@ pub fn make_cli<'a, 'b>() -> App<'a, 'b> {
                 .append_build_file_args())
         .subcommand(
             SubCommand::with_name("check")
-                .about("Check program correctness without generating object file"))
+                .alias("c")
+                .about("Check program correctness without generating object file")
+                .arg(
+                    Arg::with_name("FILE")
+                        .required(true)
+                        .help("input file to use"))
+                .arg(lint_arg()))
         .subcommand(
             SubCommand::with_name("shell")
                 .about("Generate shell completion instructions for nosh")
@@ -7,74 +7,70 @@ use inkwell::targets::{
 };
 use inkwell::OptimizationLevel;
 
-use crate::cli::NoshEmit;
-// use crate::parse;
-use crate::ast::Ptr;
 use crate::ast_pass::debug::AstTermPrinter;
-use crate::ast_pass::name_resolve::AstNameResolver;
-use crate::ast_pass::ModulePass;
-use crate::lex;
-use crate::parse::ModuleGrammer;
-use crate::parse::Parser;
+use crate::cli::check::{check_source, CheckOptions, CheckedModule};
+use crate::cli::NoshEmit;
+use crate::diag::{TermRenderer, ToDiagnostic};
 // use crate::codegen::{CodeGenerator};
 use crate::ast_pass::to_llvm::LlvmBackend;
 
-pub fn build_file(filename: &str, opt_level: OptimizationLevel, emit: Option<NoshEmit>) {
+// returns true when the requested output was produced
+pub fn build_file(
+    filename: &str,
+    opt_level: OptimizationLevel,
+    emit: Option<NoshEmit>,
+    opts: CheckOptions,
+    bounds_checks: bool,
+) -> bool {
     let path = Path::new(filename);
     let src = fs::read_to_string(&path).unwrap();
-
-    let token_buffer = lex::scan_source(src.as_str());
-    let tokens = Ptr::new(token_buffer);
-
-    let mut p = match Parser::default(tokens) {
-        Some(parser) => parser,
-        None => {
+    let renderer = TermRenderer::new(filename, src.as_str());
+
+    let CheckedModule {
+        module,
+        sym_table,
+        types,
+        warnings,
+    } = match check_source(src.as_str(), opts) {
+        Ok(Some(checked)) => checked,
+        Ok(None) => {
             eprintln!("File {} is empty, nothing to do...", filename);
-            return;
+            return true;
+        }
+        Err(diags) => {
+            renderer.emit_all(&diags);
+            return false;
         }
     };
+    renderer.emit_all(&warnings);
 
-    let tried_module = p.expect(ModuleGrammer);
+    if let Some(NoshEmit::Ast) = emit {
+        let printer = AstTermPrinter::default();
+        printer.print_module(&module);
+        return true;
+    }
 
-    let module = match tried_module {
+    let mut ll = LlvmBackend::new(opt_level);
+    if !bounds_checks {
+        ll = ll.without_bounds_checks();
+    }
+    let llmod = match ll.compile_mod(module, &sym_table, &types) {
         Ok(m) => m,
-        Err(e) => {
-            return {
-                eprintln!("{:?}", e);
-                for err in &p.errors {
-                    eprintln!("{}", err)
-                }
-            }
-        }
-    };
-
-    let sym_table = match AstNameResolver::default().run_pass(&module) {
-        Ok(table) => table,
         Err(errs) => {
-            for e in errs {
-                eprintln!("{:?}", e);
-            }
-            return;
+            let diags: Vec<_> = errs.iter().map(|e| e.to_diagnostic()).collect();
+            renderer.emit_all(&diags);
+            return false;
         }
     };
 
     match emit {
-        Some(NoshEmit::Ast) => {
-            let printer = AstTermPrinter::default();
-            printer.print_module(&module);
-        }
         Some(NoshEmit::Llvm) => {
-            let ll = LlvmBackend::new(opt_level);
-            let llmod = ll.compile_mod(module, &sym_table);
             let buffered_llvm = llmod.print_to_string();
 
             let mut stdout = io::stdout();
             stdout.write_all(buffered_llvm.to_bytes()).unwrap();
         }
         Some(NoshEmit::Asm) => {
-            let ll = LlvmBackend::new(opt_level);
-            let llmod = ll.compile_mod(module, &sym_table);
-
             let tm = prep_llvm_target_machine(opt_level);
             let buffered_asm = tm
                 .write_to_memory_buffer(&llmod, FileType::Assembly)
@@ -83,11 +79,8 @@ pub fn build_file(filename: &str, opt_level: OptimizationLevel, emit: Option<Nos
             let mut stdout = io::stdout();
             stdout.write_all(buffered_asm.as_slice()).unwrap();
         }
-
-        None => {
-            let ll = LlvmBackend::new(opt_level);
-            let llmod = ll.compile_mod(module, &sym_table);
-
+        // AST is printed before compiling
+        Some(NoshEmit::Ast) | None => {
             let savename_buf = path.with_extension("o");
             let savename = Path::new("./").join(savename_buf.file_name().unwrap());
 
@@ -96,6 +89,8 @@ pub fn build_file(filename: &str, opt_level: OptimizationLevel, emit: Option<Nos
                 .expect("Failed to write object file");
         }
     }
+
+    true
 }
 
 fn prep_llvm_target_machine(opt_level: OptimizationLevel) -> TargetMachine {
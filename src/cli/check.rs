@@ -0,0 +1,170 @@
+use std::fs;
+
+use crate::ast;
+use crate::ast::symbol::ModSymTable;
+use crate::ast::{Ptr, TypeTable};
+use crate::ast_pass::match_check::AstMatchChecker;
+use crate::ast_pass::mutability::AstMutabilityChecker;
+use crate::ast_pass::name_resolve::{AstNameResolver, NameResolution};
+use crate::ast_pass::type_check::AstTypeChecker;
+use crate::ast_pass::ModulePass;
+use crate::diag::{Diagnostic, TermRenderer, ToDiagnostic};
+use crate::lex;
+use crate::parse::{ModuleGrammer, Parser};
+
+// optional lints, all off by default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckOptions {
+    pub warn_shadowing: bool,
+}
+
+// output of the front-end, ready for a backend
+pub struct CheckedModule<'s> {
+    pub module: ast::Module<'s>,
+    pub sym_table: ModSymTable,
+    pub types: TypeTable,
+    pub warnings: Vec<Diagnostic>,
+}
+
+// Runs every front-end pass over the source
+// Ok(None) when there is nothing to compile
+pub fn check_source<'s>(
+    src: &'s str,
+    opts: CheckOptions,
+) -> Result<Option<CheckedModule<'s>>, Vec<Diagnostic>> {
+    let token_buffer = lex::scan_source(src);
+
+    let lex_errs = lex::lex_errors(&token_buffer);
+    if !lex_errs.is_empty() {
+        return Err(lex_errs);
+    }
+
+    let tokens = Ptr::new(token_buffer);
+
+    let mut p = match Parser::default(tokens) {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+
+    let module = match p.expect(ModuleGrammer) {
+        Ok(m) => m,
+        Err(e) => {
+            p.report_recovery(&e);
+            return Err(p.errors);
+        }
+    };
+
+    // the parser recovered, but the module contains error nodes
+    if !p.errors.is_empty() {
+        return Err(p.errors);
+    }
+
+    let NameResolution {
+        sym_table,
+        warnings,
+    } = match AstNameResolver::new(opts.warn_shadowing).run_pass(&module) {
+        Ok(resolved) => resolved,
+        Err(errs) => return Err(errs.iter().map(|e| e.to_diagnostic()).collect()),
+    };
+    let mut warnings: Vec<_> = warnings.iter().map(|w| w.to_diagnostic()).collect();
+
+    if let Err(errs) = AstMutabilityChecker::new(&sym_table).run_pass(&module) {
+        return Err(errs.iter().map(|e| e.to_diagnostic()).collect());
+    }
+
+    let types = match AstTypeChecker::new(&sym_table).run_pass(&module) {
+        Ok(types) => types,
+        Err(errs) => return Err(errs.iter().map(|e| e.to_diagnostic()).collect()),
+    };
+
+    match AstMatchChecker::new(&types).run_pass(&module) {
+        Ok(match_warns) => warnings.extend(match_warns.iter().map(|w| w.to_diagnostic())),
+        Err(errs) => return Err(errs.iter().map(|e| e.to_diagnostic()).collect()),
+    }
+
+    Ok(Some(CheckedModule {
+        module,
+        sym_table,
+        types,
+        warnings,
+    }))
+}
+
+// Runs every front-end pass over a file without touching LLVM
+// returns true when no errors were found
+pub fn check_file(filename: &str, opts: CheckOptions) -> bool {
+    let src = match fs::read_to_string(filename) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", filename, e);
+            return false;
+        }
+    };
+
+    let renderer = TermRenderer::new(filename, src.as_str());
+    match check_source(src.as_str(), opts) {
+        Ok(checked) => {
+            if let Some(m) = checked {
+                renderer.emit_all(&m.warnings);
+            }
+            true
+        }
+        Err(diags) => {
+            renderer.emit_all(&diags);
+            false
+        }
+    }
+}
+
+// the diagnostics of a source expected to fail checking
+#[cfg(test)]
+pub fn expect_errors(src: &str) -> Vec<Diagnostic> {
+    match check_source(src, Default::default()) {
+        Err(diags) => diags,
+        Ok(_) => panic!("expected errors in:\n{}", src),
+    }
+}
+
+// a source expected to pass every front-end pass
+#[cfg(test)]
+pub fn expect_checked(src: &str) -> CheckedModule<'_> {
+    expect_checked_with(src, Default::default())
+}
+
+#[cfg(test)]
+pub fn expect_checked_with(src: &str, opts: CheckOptions) -> CheckedModule<'_> {
+    match check_source(src, opts) {
+        Ok(Some(checked)) => checked,
+        Ok(None) => panic!("expected a module in:\n{}", src),
+        Err(diags) => panic!("unexpected errors: {:#?}", diags),
+    }
+}
+
+#[test]
+fn test_check_source_runs_every_pass() {
+    let valid = "mod m\nextern fun sin(x)\nfun f(x)\n    sin(x) * 2.0\nend\n";
+    assert!(matches!(
+        check_source(valid, Default::default()),
+        Ok(Some(_))
+    ));
+    assert!(matches!(check_source("", Default::default()), Ok(None)));
+
+    // each source fails in a later pass, which reports under its own code
+    let broken = [
+        ("mod m\nfun f()\n    'ab'\nend\n", "E0004"),
+        ("mod m\nfun f(\n    1\nend\n", "E0100"),
+        ("mod m\nfun f()\n    g()\nend\n", "E0202"),
+        ("mod m\nfun f()\n    val x = 1\n    x = 2\nend\n", "E0203"),
+        ("mod m\nfun f()\n    1 + true\nend\n", "E0301"),
+        (
+            "mod m\nfun f(b: bool)\n    match b\n    case true => 1\n    end\nend\n",
+            "E0401",
+        ),
+    ];
+    for (src, code) in broken.iter() {
+        match check_source(src, Default::default()) {
+            Err(diags) => assert!(diags.iter().any(|d| d.code == *code), "{:#?}", diags),
+            Ok(_) => panic!("expected {} in:\n{}", code, src),
+        }
+    }
+}
@@ -3,6 +3,7 @@ use rustyline::Editor;
 
 use crate::ast::Ptr;
 use crate::ast_pass::debug::AstTermPrinter;
+use crate::diag::TermRenderer;
 use crate::lex;
 use crate::parse;
 use crate::parse::{Parser, RecoveryInfo, ReplGrammer};
@@ -83,16 +84,15 @@ pub fn begin_repl() {
                         for item in items {
                             printer.print_item(&item);
                         }
+                        TermRenderer::new("<repl>", repl.as_str()).emit_all(&p.errors);
                     }
                     Err(RecoveryInfo::EarlyEOF) => {
                         repl.collect_mode();
                         continue;
                     }
                     Err(e) => {
-                        eprintln!("{:?}", e);
-                        for err in &p.errors {
-                            eprintln!("{}", err)
-                        }
+                        p.report_recovery(&e);
+                        TermRenderer::new("<repl>", repl.as_str()).emit_all(&p.errors);
                     }
                 };
 
@@ -1,71 +1,108 @@
 use inkwell::OptimizationLevel;
+use std::ffi::CStr;
 use std::fs;
-// use crate::parse;
-use crate::lex;
-use crate::parse::{ModuleGrammer, Parser};
+use std::os::raw::c_char;
 // use crate::codegen::{CodeGenerator};
-use crate::ast::Ptr;
-use crate::ast_pass::name_resolve::AstNameResolver;
+use crate::ast::{FloatWidth, ItemKind, TypeKind};
 use crate::ast_pass::to_llvm::LlvmBackend;
-use crate::ast_pass::ModulePass;
+use crate::cli::check::{check_source, CheckOptions, CheckedModule};
+use crate::diag::{TermRenderer, ToDiagnostic};
 
-pub fn run_file(filename: &str, ol: OptimizationLevel) {
+// returns true when main was found and executed
+pub fn run_file(
+    filename: &str,
+    ol: OptimizationLevel,
+    opts: CheckOptions,
+    bounds_checks: bool,
+) -> bool {
     let src = fs::read_to_string(filename).unwrap();
+    let renderer = TermRenderer::new(filename, src.as_str());
 
-    let token_buffer = lex::scan_source(src.as_str());
-    let tokens = Ptr::new(token_buffer);
-
-    let mut p = match Parser::default(tokens) {
-        Some(p) => p,
-        None => {
+    let CheckedModule {
+        module,
+        sym_table,
+        types,
+        warnings,
+    } = match check_source(src.as_str(), opts) {
+        Ok(Some(checked)) => checked,
+        Ok(None) => {
             eprintln!("File {} is empty. nothing to do...", filename);
-            return;
+            return true;
         }
-    };
-
-    let tried_module = p.expect(ModuleGrammer);
-
-    let module = match tried_module {
-        Ok(m) => m,
-        Err(e) => {
-            return {
-                eprintln!("{:?}", e);
-                for err in &p.errors {
-                    eprintln!("{}", err)
-                }
-            }
-        }
-    };
-
-    let sym_table = match AstNameResolver::default().run_pass(&module) {
-        Ok(table) => table,
-        Err(errs) => {
-            for e in errs {
-                eprintln!("{:?}", e);
-            }
-            return;
+        Err(diags) => {
+            renderer.emit_all(&diags);
+            return false;
         }
     };
+    renderer.emit_all(&warnings);
 
     // let printer = AstTermPrinter::default();
     // printer.print_module(&module);
 
-    let ll = LlvmBackend::new(OptimizationLevel::None);
+    // the JIT result is printed by the declared return type of main
+    let main_ret = module.body.iter().find_map(|item| match &item.kind {
+        ItemKind::Func(f) if f.proto.name.span == "main" => match types.lookup(&item.nid) {
+            Some(ty) => match ty.kind() {
+                TypeKind::Func { ret, .. } => Some(ret.clone()),
+                _ => None,
+            },
+            None => None,
+        },
+        _ => None,
+    });
 
-    let llmod = ll.compile_mod(module, &sym_table);
+    let mut ll = LlvmBackend::new(OptimizationLevel::None);
+    if !bounds_checks {
+        ll = ll.without_bounds_checks();
+    }
+
+    let llmod = match ll.compile_mod(module, &sym_table, &types) {
+        Ok(m) => m,
+        Err(errs) => {
+            let diags: Vec<_> = errs.iter().map(|e| e.to_diagnostic()).collect();
+            renderer.emit_all(&diags);
+            return false;
+        }
+    };
 
     let engine = llmod.create_jit_execution_engine(ol).unwrap();
 
-    match llmod.get_function("main") {
-        Some(fun) => {
+    match (llmod.get_function("main"), main_ret) {
+        (Some(fun), Some(ret_ty)) => {
             let ret_val = unsafe {
                 engine.run_function(fun, &[]) // _as_main
             };
-            let f64_ty = ll.context.f64_type();
-            println!("{}", ret_val.as_float(&f64_ty));
+            match ret_ty.kind() {
+                TypeKind::Int(_) => println!("{}", ret_val.as_int(true) as i64),
+                TypeKind::UInt(_) => println!("{}", ret_val.as_int(false)),
+                TypeKind::Bool => println!("{}", ret_val.as_int(false) != 0),
+                TypeKind::Char => match char::from_u32(ret_val.as_int(false) as u32) {
+                    Some(c) => println!("{:?}", c),
+                    None => println!("{}", ret_val.as_int(false)),
+                },
+                TypeKind::Float(FloatWidth::Float32) => {
+                    println!("{}", ret_val.as_float(&ll.context.f32_type()))
+                }
+                TypeKind::Float(FloatWidth::Float64) => {
+                    println!("{}", ret_val.as_float(&ll.context.f64_type()))
+                }
+                TypeKind::Str => {
+                    let s = unsafe { CStr::from_ptr(ret_val.into_pointer::<c_char>()) };
+                    println!("{}", s.to_string_lossy())
+                }
+                // nothing to print
+                TypeKind::Nil => (),
+                TypeKind::Func { .. } => eprintln!("main returned a function, cannot print it"),
+                TypeKind::Named(name) => eprintln!("main returned a `{}`, cannot print it", name),
+                TypeKind::Array(_) => eprintln!("main returned an array, cannot print it"),
+                TypeKind::Tuple(_) => eprintln!("main returned a tuple, cannot print it"),
+                TypeKind::Var(_) => unreachable!("type variables are resolved by type checking"),
+            }
+            true
         }
-        None => {
-            eprintln!("No function 'main' to to begin execution")
+        _ => {
+            eprintln!("No function 'main' to to begin execution");
+            false
         }
     }
 }
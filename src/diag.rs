@@ -0,0 +1,89 @@
+use crate::ast::token::SrcSpan;
+
+mod render;
+
+pub use render::TermRenderer;
+
+// codes are grouped by the compiler stage that emits them
+// E00xx lexer, E01xx parser, E02xx names and bindings, E03xx types, E09xx backend
+pub type DiagCode = &'static str;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: SrcSpan,
+    pub msg: String,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagCode,
+    pub msg: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: DiagCode, msg: String) -> Self {
+        Self {
+            severity,
+            code,
+            msg,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(code: DiagCode, msg: String) -> Self {
+        Self::new(Severity::Error, code, msg)
+    }
+
+    pub fn warning(code: DiagCode, msg: String) -> Self {
+        Self::new(Severity::Warning, code, msg)
+    }
+
+    // the location the diagnostic is about
+    pub fn with_primary(mut self, span: SrcSpan, msg: String) -> Self {
+        self.labels.push(Label {
+            span,
+            msg,
+            primary: true,
+        });
+        self
+    }
+
+    // related locations, such as a previous definition
+    pub fn with_secondary(mut self, span: SrcSpan, msg: String) -> Self {
+        self.labels.push(Label {
+            span,
+            msg,
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+// implemented by the error types of each compiler stage
+pub trait ToDiagnostic {
+    fn to_diagnostic(&self) -> Diagnostic;
+}
+
+pub fn has_errors(diags: &[Diagnostic]) -> bool {
+    diags.iter().any(|d| d.is_error())
+}
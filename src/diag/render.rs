@@ -0,0 +1,142 @@
+use std::fmt::Write;
+
+use super::{Diagnostic, Label, Severity};
+
+// Prints diagnostics in the style of
+//
+// error[E0100]: expected Ident but found `+`
+//  --> file.nosh:3:9
+//   |
+// 3 | fun foo(+)
+//   |         ^ unexpected token
+//   = note: ...
+pub struct TermRenderer<'s> {
+    filename: &'s str,
+    lines: Vec<&'s str>,
+}
+
+impl<'s> TermRenderer<'s> {
+    pub fn new(filename: &'s str, src: &'s str) -> Self {
+        Self {
+            filename,
+            lines: src.lines().collect(),
+        }
+    }
+
+    pub fn emit(&self, d: &Diagnostic) {
+        eprint!("{}", self.render(d));
+    }
+
+    pub fn emit_all(&self, diags: &[Diagnostic]) {
+        for d in diags {
+            self.emit(d);
+        }
+    }
+
+    pub fn render(&self, d: &Diagnostic) -> String {
+        let mut out = String::new();
+
+        let level = match d.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        writeln!(out, "{}[{}]: {}", level, d.code, d.msg).unwrap();
+
+        let mut labels: Vec<&Label> = d.labels.iter().collect();
+        labels.sort_by_key(|l| (l.span.start.line, l.span.start.column));
+
+        // width of the line number gutter
+        let gutter = labels
+            .iter()
+            .map(|l| l.span.start.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter);
+
+        match d.labels.iter().find(|l| l.primary).or_else(|| labels.first().copied()) {
+            Some(l) => writeln!(
+                out,
+                "{}--> {}:{}:{}",
+                pad, self.filename, l.span.start.line, l.span.start.column
+            )
+            .unwrap(),
+            None => writeln!(out, "{}--> {}", pad, self.filename).unwrap(),
+        }
+
+        if !labels.is_empty() {
+            writeln!(out, "{} |", pad).unwrap();
+        }
+
+        for label in labels {
+            self.render_label(&mut out, label, gutter);
+        }
+
+        for note in &d.notes {
+            writeln!(out, "{} = note: {}", pad, note).unwrap();
+        }
+
+        out
+    }
+
+    fn render_label(&self, out: &mut String, label: &Label, gutter: usize) {
+        let line_no = label.span.start.line;
+        let line = self
+            .lines
+            .get(line_no.saturating_sub(1) as usize)
+            .copied()
+            .unwrap_or("");
+
+        writeln!(out, "{:>width$} | {}", line_no, line, width = gutter).unwrap();
+
+        // keep tabs so the markers line up with the source text
+        let start_col = label.span.start.column.saturating_sub(1) as usize;
+        let prefix: String = line
+            .chars()
+            .chain(std::iter::repeat(' '))
+            .take(start_col)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        // spans across lines are underlined to the end of the first line
+        let line_len = line.chars().count();
+        let end_col = if label.span.end.line == line_no {
+            label.span.end.column.saturating_sub(1) as usize
+        } else {
+            line_len.max(start_col + 1)
+        };
+        let width = end_col.saturating_sub(start_col).max(1);
+
+        let marker = if label.primary { "^" } else { "-" };
+        writeln!(
+            out,
+            "{} | {}{} {}",
+            " ".repeat(gutter),
+            prefix,
+            marker.repeat(width),
+            label.msg
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_render_caret_under_token() {
+    use crate::ast::token::{SrcSpan, TokenLoc};
+
+    let src = "mod a\nfun foo(+)\nend\n";
+    let start = TokenLoc { line: 2, column: 9 };
+    let span = SrcSpan {
+        start,
+        end: start.next_col(),
+    };
+    let d = Diagnostic::error("E0100", String::from("bad token"))
+        .with_primary(span, String::from("unexpected token"));
+
+    let rendered = TermRenderer::new("a.nosh", src).render(&d);
+    let lines: Vec<_> = rendered.lines().collect();
+
+    assert_eq!(lines[0], "error[E0100]: bad token");
+    assert_eq!(lines[1], " --> a.nosh:2:9");
+    assert_eq!(lines[3], "2 | fun foo(+)");
+    assert_eq!(lines[4], "  |         ^ unexpected token");
+}
@@ -2,7 +2,8 @@ use std::collections::HashMap;
 
 use lazy_static::lazy_static;
 
-use crate::ast::token::{KwKind, ReservedKind, Token, TokenData};
+use crate::ast::token::{KwKind, LitKind, ReservedKind, Token, TokenData};
+use crate::diag::Diagnostic;
 
 mod scan;
 
@@ -11,6 +12,120 @@ pub fn scan_source(source: &str) -> Vec<TokenData> {
     tokenizer.map(process_token_kw).collect()
 }
 
+// bad tokens stay in the stream for the parser,
+// this reports them up front with their location
+pub fn lex_errors(tokens: &[TokenData]) -> Vec<Diagnostic> {
+    tokens.iter().filter_map(token_error).collect()
+}
+
+fn token_error(td: &TokenData) -> Option<Diagnostic> {
+    match td.kind {
+        Token::UnknownChunk => {
+            let msg = format!("unknown characters `{}`", td.span.escape_default());
+            let d = Diagnostic::error("E0001", msg)
+                .with_primary(td.src_span(), String::from("not valid in nosh source"));
+            Some(d)
+        }
+        Token::UnclosedStr => {
+            let d = Diagnostic::error("E0002", String::from("unterminated string literal"))
+                .with_primary(td.src_span(), String::from("missing closing `\"`"));
+            Some(d)
+        }
+        Token::UnclosedChar => {
+            let d = Diagnostic::error("E0002", String::from("unterminated character literal"))
+                .with_primary(td.src_span(), String::from("missing closing `'`"));
+            Some(d)
+        }
+        Token::Literal(LitKind::Str) => match unescape(str_contents(td.span)) {
+            Ok(_) => None,
+            Err(bad) => Some(escape_error(td, bad)),
+        },
+        Token::Literal(LitKind::Char) => match unescape(str_contents(td.span)) {
+            Ok(c) if c.chars().count() == 1 => None,
+            Ok(_) => {
+                let msg = String::from("character literal must contain exactly one character");
+                let d = Diagnostic::error("E0004", msg)
+                    .with_primary(td.src_span(), String::from("expected one character"));
+                Some(d)
+            }
+            Err(bad) => Some(escape_error(td, bad)),
+        },
+        _ => None,
+    }
+}
+
+fn escape_error(td: &TokenData, bad: &str) -> Diagnostic {
+    let msg = format!("invalid escape sequence `{}`", bad);
+    Diagnostic::error("E0003", msg)
+        .with_primary(td.src_span(), String::from("in this literal"))
+        .with_note(String::from(
+            "supported escapes are \\n \\t \\r \\0 \\\\ \\' \\\" and \\u{...}",
+        ))
+}
+
+// text between the quotes of a string or char literal
+pub fn str_contents(span: &str) -> &str {
+    &span[1..span.len() - 1]
+}
+
+// the single character of a char literal
+pub fn unescape_char(span: &str) -> Option<char> {
+    let contents = unescape(str_contents(span)).ok()?;
+    let mut chars = contents.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+// replaces escape sequences with the characters they stand for
+// the offending escape is returned when one is invalid
+pub fn unescape(raw: &str) -> Result<String, &str> {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(idx) = rest.find('\\') {
+        out.push_str(&rest[..idx]);
+        let escape = &rest[idx..];
+
+        let (c, len) = match escape[1..].chars().next() {
+            Some('n') => ('\n', 2),
+            Some('t') => ('\t', 2),
+            Some('r') => ('\r', 2),
+            Some('0') => ('\0', 2),
+            Some('\\') => ('\\', 2),
+            Some('\'') => ('\'', 2),
+            Some('"') => ('"', 2),
+            // \u{1F600}, one to six hex digits
+            Some('u') => {
+                let close = match escape.find('}') {
+                    Some(close) if escape[2..].starts_with('{') => close,
+                    _ => return Err(&escape[..2]),
+                };
+                let digits = &escape[3..close];
+                let scalar = match digits.len() {
+                    1..=6 => u32::from_str_radix(digits, 16)
+                        .ok()
+                        .and_then(char::from_u32),
+                    _ => None,
+                };
+                match scalar {
+                    Some(c) => (c, close + 1),
+                    None => return Err(&escape[..close + 1]),
+                }
+            }
+            Some(other) => return Err(&escape[..1 + other.len_utf8()]),
+            None => return Err(escape),
+        };
+
+        out.push(c);
+        rest = &escape[len..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
 fn process_token_kw(tok: TokenData) -> TokenData {
     match tok.kind {
         Token::Ident => {
@@ -28,7 +143,7 @@ fn process_token_kw(tok: TokenData) -> TokenData {
 }
 
 lazy_static! {
-    static ref _TOKEN_KW_LIST: [(&'static str, Token); 75] = [
+    static ref _TOKEN_KW_LIST: [(&'static str, Token); 77] = [
         ("fun", Token::Kw(KwKind::Fun)),
         ("extern", Token::Kw(KwKind::Extern)),
         ("end", Token::Kw(KwKind::End)),
@@ -41,7 +156,17 @@ lazy_static! {
         ("in", Token::Kw(KwKind::In)),
         ("mut", Token::Kw(KwKind::Mut)),
         ("while", Token::Kw(KwKind::While)),
-        ("loop", Token::Reserved(ReservedKind::Loop)),
+        ("loop", Token::Kw(KwKind::Loop)),
+        ("for", Token::Kw(KwKind::For)),
+        ("break", Token::Kw(KwKind::Break)),
+        ("continue", Token::Kw(KwKind::Continue)),
+        ("return", Token::Kw(KwKind::Return)),
+        ("match", Token::Kw(KwKind::Match)),
+        ("case", Token::Kw(KwKind::Case)),
+        ("struct", Token::Kw(KwKind::Struct)),
+        ("enum", Token::Kw(KwKind::Enum)),
+        ("true", Token::Literal(LitKind::Bool)),
+        ("false", Token::Literal(LitKind::Bool)),
         ("from", Token::Reserved(ReservedKind::FromKw)),
         ("import", Token::Reserved(ReservedKind::Import)),
         ("export", Token::Reserved(ReservedKind::Export)),
@@ -49,8 +174,6 @@ lazy_static! {
         ("fn", Token::Reserved(ReservedKind::Fn)),
         ("this", Token::Reserved(ReservedKind::This)),
         ("self", Token::Reserved(ReservedKind::KwSelf)),
-        ("enum", Token::Reserved(ReservedKind::Enum)),
-        ("struct", Token::Reserved(ReservedKind::Struct)),
         ("class", Token::Reserved(ReservedKind::Class)),
         ("rec", Token::Reserved(ReservedKind::Rec)),
         ("data", Token::Reserved(ReservedKind::Data)),
@@ -63,9 +186,6 @@ lazy_static! {
         ("exposing", Token::Reserved(ReservedKind::Exposing)),
         ("async", Token::Reserved(ReservedKind::Async)),
         ("await", Token::Reserved(ReservedKind::Await)),
-        ("for", Token::Reserved(ReservedKind::For)),
-        ("match", Token::Reserved(ReservedKind::Match)),
-        ("case", Token::Reserved(ReservedKind::Case)),
         ("switch", Token::Reserved(ReservedKind::Switch)),
         ("and", Token::Reserved(ReservedKind::And)),
         ("or", Token::Reserved(ReservedKind::Or)),
@@ -84,11 +204,8 @@ lazy_static! {
         ("go", Token::Reserved(ReservedKind::Go)),
         ("try", Token::Reserved(ReservedKind::Try)),
         ("catch", Token::Reserved(ReservedKind::Catch)),
-        ("break", Token::Reserved(ReservedKind::Break)),
-        ("continue", Token::Reserved(ReservedKind::Continue)),
         ("goto", Token::Reserved(ReservedKind::GoTo)),
         ("impl", Token::Reserved(ReservedKind::Impl)),
-        ("return", Token::Reserved(ReservedKind::Return)),
         ("yield", Token::Reserved(ReservedKind::Yield)),
         ("throw", Token::Reserved(ReservedKind::Throw)),
         ("raise", Token::Reserved(ReservedKind::Raise)),
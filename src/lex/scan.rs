@@ -132,8 +132,10 @@ enum LexState {
     Whitespace,
     Int,
     Float,
-    // Char,
-    // Str,
+    Char,
+    CharEscape,
+    Str,
+    StrEscape,
     // Comment,
     EOLComment,
     Sigil,
@@ -151,8 +153,8 @@ fn begin_lex_from_top(chunk: Option<char>) -> LexNext {
     if let Some(c) = chunk {
         match c {
             '/' => consume().and_trans(MaybeComment),
-            // '\'' => consume().and_trans(Char),
-            // '"'  => consume().and_trans(Str),
+            '\'' => consume().and_trans(Char),
+            '"' => consume().and_trans(Str),
             '_' => consume().and_trans(Ident),
             '\n' => consume().and_emit(Token::Newline), //consume().and_trans(Indent),
             ';' => consume().and_emit(Token::SemiColon),
@@ -160,8 +162,10 @@ fn begin_lex_from_top(chunk: Option<char>) -> LexNext {
             // '.' => consume().and_emit(Token::Dot),
             '(' => consume().and_emit(Token::OpenParen),
             ')' => consume().and_emit(Token::ClosedParen),
-            // '[' => consume().and_emit(Token::OpenSquare),
-            // ']' => consume().and_emit(Token::ClosedSquare),
+            '[' => consume().and_emit(Token::OpenSquare),
+            ']' => consume().and_emit(Token::ClosedSquare),
+            '{' => consume().and_emit(Token::OpenCurly),
+            '}' => consume().and_emit(Token::ClosedCurly),
             ',' => consume().and_emit(Token::Comma),
             ':' => consume().and_trans(MaybeColon),
             // '|' => consume().and_emit(Token::Pipe),
@@ -204,16 +208,28 @@ fn lex_next_chunk(state: LexState, chunk: Option<char>, rest: &str) -> LexNext {
         //     _ => reconsume().and_emit(Token::Indent),
         // }
 
-        // Char => match chunk{
-        //     Some('\'') => consume().and_emit(Token::Char),
-        //     None    => reconsume().and_emit(Token::UnclosedChar),
-        //     _       => consume().and_continue(),
-        // }
-        // Str => match chunk{
-        //     Some('"') => consume().and_emit(Token::Str),
-        //     None    => reconsume().and_emit(Token::UnclosedStr),
-        //     _       => consume().and_continue(),
-        // }
+        // primes inside identifiers, like x', never start a char
+        Char => match chunk {
+            Some('\'') => consume().and_emit(Token::Literal(LitKind::Char)),
+            Some('\\') => consume().and_trans(CharEscape),
+            None | Some('\n') => reconsume().and_emit(Token::UnclosedChar),
+            _ => consume().and_continue(),
+        },
+        CharEscape => match chunk {
+            None | Some('\n') => reconsume().and_emit(Token::UnclosedChar),
+            _ => consume().and_trans(Char),
+        },
+        // escapes are checked when the literal is parsed
+        Str => match chunk {
+            Some('"') => consume().and_emit(Token::Literal(LitKind::Str)),
+            Some('\\') => consume().and_trans(StrEscape),
+            None | Some('\n') => reconsume().and_emit(Token::UnclosedStr),
+            _ => consume().and_continue(),
+        },
+        StrEscape => match chunk {
+            None | Some('\n') => reconsume().and_emit(Token::UnclosedStr),
+            _ => consume().and_trans(Str),
+        },
         LexState::Int => match chunk {
             Some('.') => match rest.chars().next() {
                 Some('0'..='9') => consume().and_trans(Float),
@@ -526,3 +542,29 @@ fn test_lex_basic_ops_and_colon() {
 //     // assert_eq!(tokens[10].kind, Token::SemiColon);
 //     assert_eq!(tokens[10].kind, Token::UnknownChunk);
 // }
+
+#[test]
+fn test_lex_str_with_escaped_quote() {
+    let src = r#""say \"hi\"" "open"#;
+    let stream = Tokenizer::new(src);
+
+    let tokens: Vec<_> = stream.into_iter().collect();
+
+    assert_eq!(tokens[0].kind, Token::Literal(LitKind::Str));
+    assert_eq!(tokens[0].span, r#""say \"hi\"""#);
+    assert_eq!(tokens[2].kind, Token::UnclosedStr);
+}
+
+#[test]
+fn test_lex_char_and_primed_ident() {
+    let src = r"x' '\u{41}' '\''";
+    let stream = Tokenizer::new(src);
+
+    let tokens: Vec<_> = stream.into_iter().collect();
+
+    assert_eq!(tokens[0].kind, Token::Ident);
+    assert_eq!(tokens[0].span, "x'");
+    assert_eq!(tokens[2].kind, Token::Literal(LitKind::Char));
+    assert_eq!(tokens[2].span, r"'\u{41}'");
+    assert_eq!(tokens[4].kind, Token::Literal(LitKind::Char));
+}
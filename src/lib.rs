@@ -1,6 +1,7 @@
 pub mod ast;
 pub mod ast_pass;
 pub mod cli;
+pub mod diag;
 pub mod lex;
 pub mod parse;
 // pub mod hir;
@@ -1,7 +1,8 @@
 use crate::ast::node::NodeId;
-use crate::ast::ops::BinaryOp;
-use crate::ast::token::{Token, TokenData};
-use crate::ast::{Expr, ExprKind, Ptr};
+use crate::ast::ops::{BinaryOp, UnaryOp};
+use crate::ast::token::{SrcSpan, Token, TokenData, TokenLoc};
+use crate::ast::{Expr, ExprKind, Pattern, PatternKind, Ptr};
+use crate::diag::Diagnostic;
 
 // mod gram;
 mod op_prec;
@@ -12,8 +13,6 @@ pub use syntax::{ModuleGrammer, ReplGrammer};
 use op_prec::BinOpPrec;
 use syntax::Syntax;
 
-pub type SyntaxErrorMsg = String;
-
 // pub enum SyntaxError{
 //     InvalidBinaryOp(TokTag),
 //     InvalidUnaryOp(TokTag),
@@ -29,11 +28,21 @@ pub type SyntaxErrorMsg = String;
 #[derive(Debug, Clone)]
 pub enum RecoveryInfo {
     EarlyEOF,
-    InvalidOp,
+    InvalidOp(String),
     InvalidToken(String),
     CompilerBug,
 }
 
+impl RecoveryInfo {
+    pub fn message(&self) -> String {
+        match self {
+            RecoveryInfo::EarlyEOF => String::from("unexpected end of file"),
+            RecoveryInfo::InvalidOp(msg) | RecoveryInfo::InvalidToken(msg) => msg.clone(),
+            RecoveryInfo::CompilerBug => String::from("compiler bug in parser"),
+        }
+    }
+}
+
 pub type ParseResult<T> = Result<T, RecoveryInfo>;
 
 #[derive(Debug, Clone, Copy)]
@@ -66,7 +75,9 @@ pub struct Parser<'s> {
     // next_tag: usize, // currently unneeded to parse grammer
     tokens: Ptr<Vec<TokenData<'s>>>,
     config: ParseConfig<'s>,
-    pub errors: Vec<SyntaxErrorMsg>,
+    // end of the last consumed token
+    prev_end: TokenLoc,
+    pub errors: Vec<Diagnostic>,
 }
 
 impl<'s> Parser<'s> {
@@ -80,6 +91,7 @@ impl<'s> Parser<'s> {
             // next_tag: next_tag,
             tokens,
             config,
+            prev_end: TokenLoc::default(),
             errors: Vec::new(),
         })
     }
@@ -88,18 +100,46 @@ impl<'s> Parser<'s> {
         Self::new(ParseConfig::default(), tokens)
     }
 
-    fn report_error(&mut self, rs: RecoveryInfo, msg: String) -> RecoveryInfo {
-        self.errors.push(msg);
-        rs
+    // location of the next token, or the end of input
+    fn peek_loc(&self) -> TokenLoc {
+        match self.peek() {
+            Some(td) => td.loc,
+            None => self.prev_end,
+        }
+    }
+
+    fn peek_span(&self) -> SrcSpan {
+        match self.peek() {
+            Some(td) => td.src_span(),
+            None => SrcSpan::new(self.prev_end, self.prev_end.next_col()),
+        }
+    }
+
+    // parse errors point at the token the parser stopped on
+    pub fn diagnose(&self, info: &RecoveryInfo) -> Diagnostic {
+        let label = match info {
+            RecoveryInfo::EarlyEOF => "input ends here",
+            RecoveryInfo::InvalidOp(_) => "unsupported operator",
+            RecoveryInfo::InvalidToken(_) => "unexpected token",
+            RecoveryInfo::CompilerBug => "parser failed here",
+        };
+        Diagnostic::error("E0100", info.message()).with_primary(self.peek_span(), label.to_string())
+    }
+
+    pub fn report_recovery(&mut self, info: &RecoveryInfo) {
+        let diag = self.diagnose(info);
+        self.errors.push(diag);
     }
 
     fn peek(&self) -> Option<&TokenData<'s>> {
         self.tokens.get(self.current_tag)
     }
 
-    // fn peek_next(&mut self) -> Option<&TokenData<'s>>{
-    //     self.tokens.get(self.next_tag)
-    // }
+    // the token after the next one, for the few spots that need two tokens of lookahead
+    fn peek_next(&self) -> Option<&TokenData<'s>> {
+        let next_idx = find_next_token_idx(self.current_tag, &self.tokens)?;
+        self.tokens.get(next_idx)
+    }
 
     fn shift(&mut self) {
         // let current_idx = self.next_tag;
@@ -110,6 +150,10 @@ impl<'s> Parser<'s> {
         //     None => self.tokens.len() // ensures out of range index so all lookups will return EOF
         // };
 
+        if let Some(td) = self.peek() {
+            self.prev_end = td.src_span().end;
+        }
+
         let next_idx = match find_next_token_idx(self.current_tag, &self.tokens) {
             Some(idx) => idx,
             None => self.tokens.len(), // ensures out of range index so all lookups will return EOF
@@ -127,9 +171,37 @@ impl<'s> Parser<'s> {
         Some(*op)
     }
 
+    fn get_unary_op_from_span(&self, span: &'s str) -> Option<UnaryOp> {
+        let op = self.config.op_prec.get_unary_op_from_span(span)?;
+        Some(*op)
+    }
+
     fn get_precedence_from_span(&self, span: &'s str) -> Option<i32> {
-        let op = self.get_op_from_span(span)?;
-        Some(self.get_precedence(op))
+        match self.get_op_from_span(span) {
+            Some(op) => Some(self.get_precedence(op)),
+            None => self.config.op_prec.get_user_precedence(span),
+        }
+    }
+
+    fn is_user_op(&self, span: &'s str) -> bool {
+        self.config.op_prec.get_user_precedence(span).is_some()
+    }
+
+    fn is_user_unary_op(&self, span: &'s str) -> bool {
+        self.config.op_prec.is_user_unary_op(span)
+    }
+
+    fn is_builtin_op(&self, span: &'s str) -> bool {
+        self.config.op_prec.is_builtin(span)
+    }
+
+    // later expressions may use the op as soon as its prototype is parsed
+    fn add_user_op(&mut self, span: &'s str, prec: i32) {
+        self.config.op_prec.add_user_op(span, prec);
+    }
+
+    fn add_user_unary_op(&mut self, span: &'s str) {
+        self.config.op_prec.add_user_unary_op(span);
     }
 
     fn peek_op_precedence(&mut self) -> PeekOpPrec {
@@ -145,8 +217,15 @@ impl<'s> Parser<'s> {
         }
     }
 
-    fn new_expr(&mut self, kind: ExprKind<'s>) -> Expr<'s> {
-        Expr::new(self.nid.shift(), kind)
+    // expression spans run from start up to the last consumed token
+    fn new_expr(&mut self, start: TokenLoc, kind: ExprKind<'s>) -> Expr<'s> {
+        let span = SrcSpan::new(start, self.prev_end);
+        Expr::new(self.nid.shift(), span, kind)
+    }
+
+    fn new_pattern(&mut self, start: TokenLoc, kind: PatternKind<'s>) -> Pattern<'s> {
+        let span = SrcSpan::new(start, self.prev_end);
+        Pattern::new(self.nid.shift(), span, kind)
     }
 
     fn check<S: Syntax<'s>>(&self, s: S) -> bool {
@@ -1,17 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::ast::ops::BinaryOp;
+use crate::ast::ops::{BinaryOp, UnaryOp};
 
 // Lifetime will be useful for parsing custom ops
 #[derive(Debug, Clone)]
 pub struct BinOpPrec<'s> {
     // prec: HashMap<BinaryOp, i32>,
     ops: HashMap<&'s str, BinaryOp>,
+    // prefix ops bind tighter than every binary level
+    unary_ops: HashMap<&'s str, UnaryOp>,
+    // declared with `fun binary|> 5 (a, b)`, called like functions
+    user_ops: HashMap<&'s str, i32>,
+    // declared with `fun unary~ (x)`
+    user_unary_ops: HashSet<&'s str>,
 }
 
 impl<'s> BinOpPrec<'s> {
     pub fn init() -> Self {
-        let num_ops = 10;
+        let num_ops = 19;
         // let mut prec_map = HashMap::with_capacity(num_ops);
         // let mut count = 1;
 
@@ -42,29 +48,69 @@ impl<'s> BinOpPrec<'s> {
         ops_map.insert("-", BinaryOp::Sub);
         ops_map.insert("*", BinaryOp::Mul);
         ops_map.insert("/", BinaryOp::Div);
+        ops_map.insert("%", BinaryOp::Rem);
+        ops_map.insert("&", BinaryOp::BitAnd);
+        ops_map.insert("|", BinaryOp::BitOr);
+        ops_map.insert("^", BinaryOp::BitXor);
+        ops_map.insert("<<", BinaryOp::Shl);
+        ops_map.insert(">>", BinaryOp::Shr);
         ops_map.insert("&&", BinaryOp::LogicalAnd);
         ops_map.insert("||", BinaryOp::LogicalOr);
         ops_map.insert("<=", BinaryOp::LtEt);
         ops_map.insert("<", BinaryOp::Lt);
+        ops_map.insert(">=", BinaryOp::GtEt);
+        ops_map.insert(">", BinaryOp::Gt);
         ops_map.insert("==", BinaryOp::Et);
+        ops_map.insert("!=", BinaryOp::NotEt);
+
+        let mut unary_map = HashMap::with_capacity(2);
+        unary_map.insert("-", UnaryOp::Neg);
+        unary_map.insert("!", UnaryOp::LogicalNot);
 
         Self {
             ops: ops_map,
+            unary_ops: unary_map,
+            user_ops: HashMap::new(),
+            user_unary_ops: HashSet::new(),
             // prec: prec_map,
         }
     }
 
+    // gaps between levels leave room for user defined ops
     pub fn get_precedence(&self, bo: BinaryOp) -> i32 {
         use BinaryOp::*;
         match bo {
-            Assign => 1,
-            LogicalAnd | LogicalOr => 2,
-            Et | Lt | LtEt => 3,
-            Add | Sub => 4,
-            Mul | Div => 5,
+            Assign => 10,
+            LogicalAnd | LogicalOr => 20,
+            // below comparisons like C
+            BitOr => 24,
+            BitXor => 25,
+            BitAnd => 26,
+            Et | NotEt | Lt | LtEt | Gt | GtEt => 30,
+            Shl | Shr => 35,
+            Add | Sub => 40,
+            Mul | Div | Rem => 50,
         }
     }
+    pub fn is_builtin(&self, op: &'s str) -> bool {
+        self.ops.contains_key(op) || self.unary_ops.contains_key(op)
+    }
+    pub fn add_user_op(&mut self, op: &'s str, prec: i32) {
+        self.user_ops.insert(op, prec);
+    }
+    pub fn add_user_unary_op(&mut self, op: &'s str) {
+        self.user_unary_ops.insert(op);
+    }
+    pub fn get_user_precedence(&self, op: &'s str) -> Option<i32> {
+        self.user_ops.get(op).copied()
+    }
+    pub fn is_user_unary_op(&self, op: &'s str) -> bool {
+        self.user_unary_ops.contains(op)
+    }
     pub fn get_op_from_span(&self, op: &'s str) -> Option<&BinaryOp> {
         self.ops.get(op)
     }
+    pub fn get_unary_op_from_span(&self, op: &'s str) -> Option<&UnaryOp> {
+        self.unary_ops.get(op)
+    }
 }
@@ -5,6 +5,7 @@ mod comb;
 mod delim;
 mod stmt;
 mod top;
+mod types;
 
 pub use top::{ModuleGrammer, ReplGrammer};
 
@@ -67,8 +68,10 @@ impl<'s> Syntax<'s> for Token {
                     Ok(final_token)
                 } else {
                     let msg = format!(
-                        "Expected token {:?} But Found {:?} at {:?}",
-                        self, td.kind, td.loc
+                        "Expected token {:?} But Found {:?} `{}`",
+                        self,
+                        td.kind,
+                        td.span.escape_default()
                     );
                     Err(RecoveryInfo::InvalidToken(msg))
                 }
@@ -1,4 +1,4 @@
-use super::{Delimiter, Syntax};
+use super::{Delimiter, InvalidSyntax, Syntax};
 use crate::ast::token::{KwKind, Token};
 use crate::parse::{ParseResult, Parser, RecoveryInfo};
 
@@ -264,3 +264,38 @@ where
         Ok(content)
     }
 }
+
+// Parse S, on failure record the error and let I skip
+// to a synchronization point so parsing can continue
+// EOF is not recovered from, the REPL relies on seeing it
+pub struct Recover<S, I>(pub S, pub I);
+impl<'s, S, I> Syntax<'s> for Recover<S, I>
+where
+    S: Syntax<'s>,
+    I: InvalidSyntax<'s> + Syntax<'s, Parsed = S::Parsed>,
+{
+    type Parsed = S::Parsed;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(&self.0)
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let before = p.current_tag;
+
+        match p.expect(&self.0) {
+            Ok(parsed) => Ok(parsed),
+            Err(RecoveryInfo::EarlyEOF) => Err(RecoveryInfo::EarlyEOF),
+            Err(e) => {
+                p.report_recovery(&e);
+
+                // always make progress, even if S failed on its first token
+                if p.current_tag == before {
+                    p.shift();
+                }
+
+                p.expect(&self.1)
+            }
+        }
+    }
+}
@@ -22,4 +22,6 @@ macro_rules! delimiter_pair {
 
 delimiter_pair! {
     (pub struct ParenDelim, Token::OpenParen, Token::ClosedParen),
+    (pub struct SquareDelim, Token::OpenSquare, Token::ClosedSquare),
+    (pub struct CurlyDelim, Token::OpenCurly, Token::ClosedCurly),
 }
@@ -1,14 +1,18 @@
 use super::comb::{
-    AnyOf, DelimitedListOf, Either, EitherOf, EndedOf, LineEnd, LineOf, LinesOf, PairOf,
+    AnyOf, DelimitedListOf, Either, EitherOf, EndedOf, LineEnd, LineOf, LinesOf, PairOf, Recover,
 };
-use super::delim::ParenDelim;
-use super::Syntax;
+use super::delim::{CurlyDelim, ParenDelim, SquareDelim};
+use super::types::TypeAnnotation;
+use super::{InvalidSyntax, Syntax};
 
+use crate::lex;
 use crate::parse::{ParseResult, Parser, PeekOpPrec, RecoveryInfo};
 
 use crate::ast::ops::BinaryOp;
-use crate::ast::token::{KwKind, LitKind, Token};
-use crate::ast::{Expr, ExprKind, MutKind, VarDecl};
+use crate::ast::token::{KwKind, LitKind, Token, TokenData, TokenLoc};
+use crate::ast::{
+    Expr, ExprKind, FieldInit, ForRange, Lit, MatchArm, MutKind, Pattern, PatternKind, VarDecl,
+};
 
 struct LiteralExpr;
 impl<'s> Syntax<'s> for LiteralExpr {
@@ -20,35 +24,60 @@ impl<'s> Syntax<'s> for LiteralExpr {
             Token::Literal(LitKind::Int),
             Token::Literal(LitKind::Char),
             Token::Literal(LitKind::Str),
+            Token::Literal(LitKind::Bool),
         ]))
     }
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
         let found_lit = p.expect(AnyOf(&[
             Token::Literal(LitKind::Float),
             Token::Literal(LitKind::Int),
             Token::Literal(LitKind::Char),
             Token::Literal(LitKind::Str),
+            Token::Literal(LitKind::Bool),
         ]))?;
 
-        match found_lit.kind {
-            Token::Literal(lit) => match lit {
-                LitKind::Float => {
-                    let fp = found_lit.span.parse().unwrap();
-                    Ok(p.new_expr(ExprKind::Lit(fp)))
+        let lit = parse_lit(found_lit)?;
+        Ok(p.new_expr(start, ExprKind::Lit(lit)))
+    }
+}
+
+// value of a literal token
+fn parse_lit(found_lit: TokenData) -> ParseResult<Lit> {
+    let lit = match found_lit.kind {
+        Token::Literal(lit) => match lit {
+            LitKind::Float => Lit::Float(found_lit.span.parse().unwrap()),
+            LitKind::Int => match found_lit.span.parse::<i64>() {
+                Ok(int) => Lit::Int(int),
+                Err(_) => {
+                    let msg = format!("Integer literal {} does not fit in i64", found_lit.span);
+                    return Err(RecoveryInfo::InvalidToken(msg));
                 }
-                LitKind::Int => {
-                    let fp = found_lit.span.parse().unwrap();
-                    Ok(p.new_expr(ExprKind::Lit(fp)))
+            },
+            LitKind::Bool => Lit::Bool(found_lit.span == "true"),
+            // bad escapes were reported with the lexer errors
+            LitKind::Char => match lex::unescape_char(found_lit.span) {
+                Some(c) => Lit::Char(c),
+                None => {
+                    let msg = format!("Invalid character literal {}", found_lit.span);
+                    return Err(RecoveryInfo::InvalidToken(msg));
                 }
-                _ => unimplemented!("Invalid literal, only float and int supported"),
             },
-            _ => unreachable!(),
-        }
-    }
+            LitKind::Str => match lex::unescape(lex::str_contents(found_lit.span)) {
+                Ok(s) => Lit::Str(s),
+                Err(bad) => {
+                    let msg = format!("Invalid escape sequence `{}`", bad);
+                    return Err(RecoveryInfo::InvalidToken(msg));
+                }
+            },
+        },
+        _ => unreachable!(),
+    };
+    Ok(lit)
 }
 
-// parentheses are always tuples
+// parentheses are tuples, around a single expr they only group it
 struct TupleExpr;
 impl<'s> Syntax<'s> for TupleExpr {
     type Parsed = Expr<'s>;
@@ -58,16 +87,35 @@ impl<'s> Syntax<'s> for TupleExpr {
     }
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
-        let body = p.expect(DelimitedListOf(ParenDelim, Expression))?;
-
-        if body.len() == 1 {
-            Ok(body.into_iter().next().unwrap())
-        } else {
-            unimplemented!("Tuples with multiple elements are not supported yet")
+        let start = p.peek_loc();
+        let mut body = p.expect(DelimitedListOf(ParenDelim, Expression))?;
+
+        match body.len() {
+            0 => Err(RecoveryInfo::InvalidToken(String::from(
+                "Empty tuples are not supported",
+            ))),
+            1 => Ok(body.pop().unwrap()),
+            _ => Ok(p.new_expr(start, ExprKind::TupleLit(body))),
         }
     }
 }
 
+// `[1.0, 2.0]`, elements may span lines
+struct ArrayExpr;
+impl<'s> Syntax<'s> for ArrayExpr {
+    type Parsed = Expr<'s>;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::OpenSquare)
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
+        let elems = p.expect(DelimitedListOf(SquareDelim, Expression))?;
+        Ok(p.new_expr(start, ExprKind::ArrayLit(elems)))
+    }
+}
+
 struct LocalVar;
 impl<'s> Syntax<'s> for LocalVar {
     type Parsed = Expr<'s>;
@@ -78,7 +126,7 @@ impl<'s> Syntax<'s> for LocalVar {
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
         let ident = p.expect(Token::Ident)?;
-        Ok(p.new_expr(ExprKind::Var(ident)))
+        Ok(p.new_expr(ident.loc, ExprKind::Var(ident)))
     }
 }
 
@@ -90,19 +138,113 @@ impl<'s> Syntax<'s> for UnaryExpr {
         p.check(Token::Sigil)
     }
 
+    // the operand is a primary expr, so `-f(x) * y` is `(-(f(x))) * y`
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
-        let _op = p.expect(Token::Sigil)?;
-        let _body = p.expect(PrimaryExpr)?;
-        unimplemented!("unary expression not implemented")
+        let start = p.peek_loc();
+        let op_tok = p.expect(Token::Sigil)?;
+        let op = match p.get_unary_op_from_span(op_tok.span) {
+            Some(op) => op,
+            None if p.is_user_unary_op(op_tok.span) => {
+                let operand = p.expect(PrimaryExpr)?;
+                return Ok(user_op_call(p, start, op_tok, vec![operand]));
+            }
+            None => {
+                let msg = format!("unsupported unary operator {:?}", op_tok.span);
+                return Err(RecoveryInfo::InvalidOp(msg));
+            }
+        };
+
+        let operand = p.expect(PrimaryExpr)?;
+        Ok(p.new_expr(start, ExprKind::Unary { op, operand }))
     }
 }
 
+// user defined ops are calls to the function named by the op
+fn user_op_call<'s>(
+    p: &mut Parser<'s>,
+    start: TokenLoc,
+    op_tok: TokenData<'s>,
+    args: Vec<Expr<'s>>,
+) -> Expr<'s> {
+    let callee = Expr::new(p.nid.shift(), op_tok.src_span(), ExprKind::Var(op_tok));
+    p.new_expr(start, ExprKind::Call { callee, args })
+}
+
 // Grammer fragments, not full syntax
 // A struct implementation would require a Expr as a field
 // which breaks the borrow-checking model of this implementation style
 fn parse_call_expr<'s>(p: &mut Parser<'s>, callee: Expr<'s>) -> ParseResult<Expr<'s>> {
+    let start = callee.span.start;
     let args = p.expect(DelimitedListOf(ParenDelim, Expression))?;
-    Ok(p.new_expr(ExprKind::Call { callee, args }))
+    Ok(p.new_expr(start, ExprKind::Call { callee, args }))
+}
+
+// `x: 1.0` in a struct literal
+struct FieldInitSyntax;
+impl<'s> Syntax<'s> for FieldInitSyntax {
+    type Parsed = FieldInit<'s>;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Ident)
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let name = p.expect(Token::Ident)?;
+        p.expect(Token::ColonSingle)?;
+        let value = p.expect(Expression)?;
+        Ok(FieldInit { name, value })
+    }
+}
+
+// Grammer fragments, not full syntax
+fn parse_struct_lit<'s>(p: &mut Parser<'s>, lead: Expr<'s>) -> ParseResult<Expr<'s>> {
+    let start = lead.span.start;
+    let name = match *lead.kind {
+        ExprKind::Var(name) => name,
+        _ => {
+            let msg = String::from("Struct literals must start with the struct name");
+            return Err(RecoveryInfo::InvalidToken(msg));
+        }
+    };
+    let fields = p.expect(DelimitedListOf(CurlyDelim, FieldInitSyntax))?;
+    Ok(p.new_expr(start, ExprKind::StructLit { name, fields }))
+}
+
+// Grammer fragments, not full syntax
+fn parse_index_expr<'s>(p: &mut Parser<'s>, base: Expr<'s>) -> ParseResult<Expr<'s>> {
+    let start = base.span.start;
+    p.expect(Token::OpenSquare)?;
+    let index = p.expect(Expression)?;
+    p.expect(Token::ClosedSquare)?;
+    Ok(p.new_expr(start, ExprKind::Index { base, index }))
+}
+
+// Grammer fragments, not full syntax
+// `t.0.1` is lexed as `t`, `.` and the float `0.1`, which indexes twice
+fn parse_tuple_field<'s>(p: &mut Parser<'s>, base: Expr<'s>) -> ParseResult<Expr<'s>> {
+    let start = base.span.start;
+    p.expect(Token::Dot)?;
+    let td = p.expect(AnyOf(&[
+        Token::Literal(LitKind::Int),
+        Token::Literal(LitKind::Float),
+    ]))?;
+
+    let mut new_lead = base;
+    for part in td.span.split('.') {
+        let index = match part.parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => {
+                let msg = format!("Invalid tuple index `{}`", part);
+                return Err(RecoveryInfo::InvalidToken(msg));
+            }
+        };
+        let kind = ExprKind::TupleField {
+            base: new_lead,
+            index,
+        };
+        new_lead = p.new_expr(start, kind);
+    }
+    Ok(new_lead)
 }
 
 // Grammer fragments, not full syntax
@@ -116,6 +258,31 @@ fn parse_trailing_expr<'s>(p: &mut Parser<'s>, first_lead: Expr<'s>) -> ParseRes
                 Token::OpenParen => {
                     new_lead = parse_call_expr(p, new_lead)?;
                 }
+                Token::OpenCurly => {
+                    new_lead = parse_struct_lit(p, new_lead)?;
+                }
+                Token::OpenSquare => {
+                    new_lead = parse_index_expr(p, new_lead)?;
+                }
+                // `0..n` is a range, not a field
+                Token::Dot if p.peek_next().map(|td| td.kind) == Some(Token::Ident) => {
+                    let start = new_lead.span.start;
+                    p.expect(Token::Dot)?;
+                    let field = p.expect(Token::Ident)?;
+                    let kind = ExprKind::Field {
+                        base: new_lead,
+                        field,
+                    };
+                    new_lead = p.new_expr(start, kind);
+                }
+                Token::Dot
+                    if matches!(
+                        p.peek_next().map(|td| td.kind),
+                        Some(Token::Literal(LitKind::Int)) | Some(Token::Literal(LitKind::Float))
+                    ) =>
+                {
+                    new_lead = parse_tuple_field(p, new_lead)?;
+                }
                 _ => return Ok(new_lead),
             },
             _ => return Ok(new_lead),
@@ -129,7 +296,14 @@ impl<'s> Syntax<'s> for PrimaryExpr {
 
     fn check(&self, p: &Parser<'s>) -> bool {
         match p.peek() {
-            Some(td) => matches!(td.kind, Token::OpenParen | Token::Literal(_) | Token::Ident),
+            Some(td) => matches!(
+                td.kind,
+                Token::OpenParen
+                    | Token::OpenSquare
+                    | Token::Literal(_)
+                    | Token::Ident
+                    | Token::Sigil
+            ),
             _ => false,
         }
     }
@@ -138,9 +312,11 @@ impl<'s> Syntax<'s> for PrimaryExpr {
         let lead = match p.peek() {
             Some(tok) => match tok.kind {
                 Token::OpenParen => p.expect(TupleExpr)?,
+                Token::OpenSquare => p.expect(ArrayExpr)?,
                 Token::Ident => p.expect(LocalVar)?,
                 Token::Literal(_) => p.expect(LiteralExpr)?,
-                Token::Sigil => unimplemented!("No unary ops supported"),
+                // the operand already includes trailing calls
+                Token::Sigil => return p.expect(UnaryExpr),
                 _ => {
                     return Err(RecoveryInfo::InvalidToken(format!(
                         "Cannot parse token {:?} as part of an expr",
@@ -181,12 +357,9 @@ fn parse_binary_rhs<'s>(p: &mut Parser<'s>, lhs: Expr<'s>, old_prec: i32) -> Par
                 match tok {
                     Some(td) => {
                         let msg = format!("unsupported operator {:?}", td.span);
-                        Err(p.report_error(RecoveryInfo::InvalidOp, msg))
-                    }
-                    None => {
-                        let msg = format!("Early EOF while parsing BinaryExpr");
-                        Err(p.report_error(RecoveryInfo::InvalidOp, msg))
+                        Err(RecoveryInfo::InvalidOp(msg))
                     }
+                    None => Err(RecoveryInfo::EarlyEOF),
                 }
             }
         }
@@ -210,16 +383,13 @@ fn parse_binary_rhs<'s>(p: &mut Parser<'s>, lhs: Expr<'s>, old_prec: i32) -> Par
                 match tok {
                     Some(td) => {
                         let msg = format!("unsupported operator {:?}", td.span);
-                        Err(p.report_error(RecoveryInfo::InvalidOp, msg))
-                    }
-                    None => {
-                        let msg = format!("Early EOF while parsing BinaryExpr");
-                        Err(p.report_error(RecoveryInfo::InvalidOp, msg))
+                        Err(RecoveryInfo::InvalidOp(msg))
                     }
+                    None => Err(RecoveryInfo::EarlyEOF),
                 }
             }
         }
-        PeekOpPrec::ExprEnd => return Ok(p.new_expr(ExprKind::Binary { op, lhs, rhs })),
+        PeekOpPrec::ExprEnd => return Ok(build_infix_expr(p, op, lhs, rhs)),
     };
 
     // if this op binds less tightly, compute next expr first
@@ -227,38 +397,63 @@ fn parse_binary_rhs<'s>(p: &mut Parser<'s>, lhs: Expr<'s>, old_prec: i32) -> Par
         rhs = parse_binary_rhs(p, rhs, prec + 1)?;
     }
 
-    let new_lhs = p.new_expr(ExprKind::Binary { op, lhs, rhs });
+    let new_lhs = build_infix_expr(p, op, lhs, rhs);
 
     // TODO: Tail Call Opt
     parse_binary_rhs(p, new_lhs, old_prec)
 }
 
-fn parse_binary_op(p: &mut Parser) -> ParseResult<BinaryOp> {
+enum InfixOp<'s> {
+    Builtin(BinaryOp),
+    User(TokenData<'s>),
+}
+
+fn parse_binary_op<'s>(p: &mut Parser<'s>) -> ParseResult<InfixOp<'s>> {
     let td = p.expect(AnyOf(&[Token::Sigil, Token::Assigner]))?;
 
     let op = match p.get_op_from_span(td.span) {
-        Some(op) => op,
-        _ => return Err(RecoveryInfo::InvalidOp),
-        // p.report_error(format!("unsupported operator {:?}", td.span))),
+        Some(op) => InfixOp::Builtin(op),
+        None if p.is_user_op(td.span) => InfixOp::User(td),
+        _ => {
+            let msg = format!("unsupported operator {:?}", td.span);
+            return Err(RecoveryInfo::InvalidOp(msg));
+        }
     };
     Ok(op)
 }
 
+fn build_infix_expr<'s>(
+    p: &mut Parser<'s>,
+    op: InfixOp<'s>,
+    lhs: Expr<'s>,
+    rhs: Expr<'s>,
+) -> Expr<'s> {
+    let start = lhs.span.start;
+    match op {
+        InfixOp::Builtin(op) => p.new_expr(start, ExprKind::Binary { op, lhs, rhs }),
+        InfixOp::User(td) => user_op_call(p, start, td, vec![lhs, rhs]),
+    }
+}
+
 // Python style trailing If syntax
 // this is a clause on a BinaryExpr, not a PimaryExpr
 // mutually-recursive nature allows for parsing nested-if-clause
 fn parse_trailing_if_expr<'s>(p: &mut Parser<'s>, if_body: Expr<'s>) -> ParseResult<Expr<'s>> {
+    let start = if_body.span.start;
     p.expect(Token::Kw(KwKind::If))?;
     let cond = p.expect(BinaryExpr)?;
 
     p.expect(Token::Kw(KwKind::Else))?;
     let else_body = p.expect(BinaryExpr)?;
 
-    Ok(p.new_expr(ExprKind::If {
-        cond,
-        if_body,
-        else_body,
-    }))
+    Ok(p.new_expr(
+        start,
+        ExprKind::If {
+            cond,
+            if_body,
+            else_body,
+        },
+    ))
 }
 
 struct BinaryExpr;
@@ -295,12 +490,23 @@ impl<'s> Syntax<'s> for IfExpr {
     }
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
-        let block_start_grammer = PairOf(Token::Kw(KwKind::If), LineOf(BinaryExpr));
-        let (_, cond) = p.expect(block_start_grammer)?;
+        let start = p.peek_loc();
+        p.expect(Token::Kw(KwKind::If))?;
+
+        // `if let pattern = value` matches the value instead of testing it
+        let if_let = match p.parse_if_present(Token::Kw(KwKind::Let)) {
+            Some(_) => {
+                let pat = p.expect(MatchPattern)?;
+                p.expect(Token::Assigner)?;
+                Some(pat)
+            }
+            None => None,
+        };
+        let cond = p.expect(LineOf(BinaryExpr))?;
 
         let if_body = p.expect(BlockBody)?;
 
-        p.expect(Token::Kw(KwKind::Else))?;
+        let else_td = p.expect(Token::Kw(KwKind::Else))?;
 
         // sugar to avoid end cascade in "else if" stmts
 
@@ -312,11 +518,28 @@ impl<'s> Syntax<'s> for IfExpr {
             Either::Second((_, s)) => s,
         };
 
-        Ok(p.new_expr(ExprKind::If {
-            cond,
-            if_body,
-            else_body,
-        }))
+        // sugar for a `match` whose other arm is the else body
+        if let Some(pat) = if_let {
+            let rest = Pattern::new(p.nid.shift(), else_td.src_span(), PatternKind::Wildcard);
+            let arms = vec![
+                MatchArm { pat, body: if_body },
+                MatchArm {
+                    pat: rest,
+                    body: else_body,
+                },
+            ];
+            let scrutinee = cond;
+            return Ok(p.new_expr(start, ExprKind::Match { scrutinee, arms }));
+        }
+
+        Ok(p.new_expr(
+            start,
+            ExprKind::If {
+                cond,
+                if_body,
+                else_body,
+            },
+        ))
     }
 }
 
@@ -337,16 +560,48 @@ impl<'s> Syntax<'s> for VarDeclBody {
         };
 
         let bound = p.expect(Token::Ident)?;
+        let ty = p.parse_if_present(TypeAnnotation).transpose()?;
+
+        p.expect(Token::Assigner)?;
+
+        let value = p.expect(Expression)?;
+
+        Ok(VarDecl::new(mutable, bound, ty, value))
+    }
+}
+
+// `(lo, hi) = pair` after `val`, with an optional `mut` before it
+struct DestructureBody;
+impl<'s> Syntax<'s> for DestructureBody {
+    type Parsed = ExprKind<'s>;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        match p.peek().map(|td| td.kind) {
+            Some(Token::OpenParen) => true,
+            Some(Token::Kw(KwKind::Mut)) => {
+                p.peek_next().map(|td| td.kind) == Some(Token::OpenParen)
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let mutable = match p.parse_if_present(Token::Kw(KwKind::Mut)) {
+            Some(_) => MutKind::Mutable,
+            None => MutKind::Const,
+        };
 
-        // TODO optionally parse type
+        let pat = p.expect(MatchPattern)?;
+        let ty = p.parse_if_present(TypeAnnotation).transpose()?;
 
         p.expect(Token::Assigner)?;
 
         let value = p.expect(Expression)?;
 
-        Ok(VarDecl {
+        Ok(ExprKind::Destructure {
             mutable,
-            bound,
+            pat,
+            ty,
             value,
         })
     }
@@ -361,11 +616,17 @@ impl<'s> Syntax<'s> for ValMutDecl {
     }
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
         p.expect(Token::Kw(KwKind::Val))?;
 
+        if p.check(DestructureBody) {
+            let kind = p.expect(DestructureBody)?;
+            return Ok(p.new_expr(start, kind));
+        }
+
         let decl = p.expect(VarDeclBody)?;
 
-        Ok(p.new_expr(ExprKind::Decl(decl)))
+        Ok(p.new_expr(start, ExprKind::Decl(decl)))
     }
 }
 
@@ -378,6 +639,7 @@ impl<'s> Syntax<'s> for LetExpr {
     }
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
         p.expect(Token::Kw(KwKind::Let))?;
 
         let binding_parts = p.expect(LinesOf(VarDeclBody))?;
@@ -402,10 +664,10 @@ impl<'s> Syntax<'s> for LetExpr {
 
         let bound = binding_parts
             .into_iter()
-            .map(|b| p.new_expr(ExprKind::Decl(b)))
+            .map(|b| p.new_expr(b.bound.loc, ExprKind::Decl(b)))
             .collect();
 
-        Ok(p.new_expr(ExprKind::Let { bound, let_body }))
+        Ok(p.new_expr(start, ExprKind::Let { bound, let_body }))
     }
 }
 
@@ -423,6 +685,11 @@ impl<'s> Syntax<'s> for DoExpr {
     }
 }
 
+fn bad_keyword(kw: KwKind, context: &str) -> RecoveryInfo {
+    let msg = format!("Keyword {:?} cannot start {}", kw, context);
+    RecoveryInfo::InvalidToken(msg)
+}
+
 struct Expression;
 impl<'s> Syntax<'s> for Expression {
     type Parsed = Expr<'s>;
@@ -430,10 +697,10 @@ impl<'s> Syntax<'s> for Expression {
     fn check(&self, p: &Parser<'s>) -> bool {
         match p.peek() {
             Some(tok) => match tok.kind {
-                Token::Kw(kw) => match kw {
-                    KwKind::If | KwKind::Let | KwKind::Do => true,
-                    _ => unimplemented!("Bad Keyword {:?}", kw),
-                },
+                Token::Kw(kw) => matches!(
+                    kw,
+                    KwKind::If | KwKind::Let | KwKind::Do | KwKind::Loop | KwKind::Match
+                ),
                 _ => p.check(BinaryExpr),
             },
             None => false,
@@ -447,7 +714,9 @@ impl<'s> Syntax<'s> for Expression {
                     KwKind::If => p.expect(IfExpr),
                     KwKind::Let => p.expect(LetExpr),
                     KwKind::Do => p.expect(DoExpr),
-                    _ => unimplemented!("Bad Keyword {:?}", kw),
+                    KwKind::Loop => p.expect(LoopExpr),
+                    KwKind::Match => p.expect(MatchExpr),
+                    _ => Err(bad_keyword(kw, "an expression")),
                 },
                 _ => p.expect(BinaryExpr),
             },
@@ -464,10 +733,236 @@ impl<'s> Syntax<'s> for WhileStmt {
     }
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
         p.expect(Token::Kw(KwKind::While))?;
         let cond = p.expect(LineOf(BinaryExpr))?;
         let while_body = p.expect(StmtBlock)?;
-        Ok(p.new_expr(ExprKind::While { cond, while_body }))
+        Ok(p.new_expr(start, ExprKind::While { cond, while_body }))
+    }
+}
+
+struct LoopExpr;
+impl<'s> Syntax<'s> for LoopExpr {
+    type Parsed = Expr<'s>;
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(LineOf(Token::Kw(KwKind::Loop)))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
+        p.expect(LineOf(Token::Kw(KwKind::Loop)))?;
+        let loop_body = p.expect(StmtBlock)?;
+        Ok(p.new_expr(start, ExprKind::Loop(loop_body)))
+    }
+}
+
+// `_`, a name, a variant with patterns for its payload,
+// a tuple of patterns, or a literal with an optional `-`
+struct MatchPattern;
+impl<'s> Syntax<'s> for MatchPattern {
+    type Parsed = Pattern<'s>;
+    fn check(&self, p: &Parser<'s>) -> bool {
+        match p.peek() {
+            Some(td) => match td.kind {
+                Token::Ident | Token::Literal(_) | Token::OpenParen => true,
+                Token::Sigil => td.span == "-",
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
+
+        // parentheses around a single pattern only group it
+        if p.check(Token::OpenParen) {
+            let mut elems = p.expect(DelimitedListOf(ParenDelim, MatchPattern))?;
+            return match elems.len() {
+                0 => Err(RecoveryInfo::InvalidToken(String::from(
+                    "Empty tuple patterns are not supported",
+                ))),
+                1 => Ok(elems.pop().unwrap()),
+                _ => Ok(p.new_pattern(start, PatternKind::Tuple(elems))),
+            };
+        }
+
+        let negative = match p.peek() {
+            Some(td) if td.kind == Token::Sigil && td.span == "-" => {
+                p.shift();
+                true
+            }
+            _ => false,
+        };
+        let td = p.expect(AnyOf(&[
+            Token::Ident,
+            Token::Literal(LitKind::Float),
+            Token::Literal(LitKind::Int),
+            Token::Literal(LitKind::Char),
+            Token::Literal(LitKind::Bool),
+            Token::Literal(LitKind::Str),
+        ]))?;
+
+        let kind = match td.kind {
+            Token::Ident if negative => {
+                let msg = format!("Only number patterns can be negative, found `-{}`", td.span);
+                return Err(RecoveryInfo::InvalidToken(msg));
+            }
+            Token::Ident if td.span == "_" => PatternKind::Wildcard,
+            Token::Ident if p.check(Token::OpenParen) => {
+                let args = p.expect(DelimitedListOf(ParenDelim, MatchPattern))?;
+                PatternKind::Variant { name: td, args }
+            }
+            Token::Ident => PatternKind::Bind(td),
+            _ => match (parse_lit(td)?, negative) {
+                (Lit::Str(_), _) => {
+                    let msg = String::from("String patterns are not supported");
+                    return Err(RecoveryInfo::InvalidToken(msg));
+                }
+                (Lit::Int(int), true) => PatternKind::Lit(Lit::Int(-int)),
+                (Lit::Float(float), true) => PatternKind::Lit(Lit::Float(-float)),
+                (lit, false) => PatternKind::Lit(lit),
+                (_, true) => {
+                    let msg = format!("Only number patterns can be negative, found `-{}`", td.span);
+                    return Err(RecoveryInfo::InvalidToken(msg));
+                }
+            },
+        };
+        Ok(p.new_pattern(start, kind))
+    }
+}
+
+// the arm body is the statements up to the next `case`
+// the first one can follow the `=>` on the same line
+struct MatchArmSyntax;
+impl<'s> Syntax<'s> for MatchArmSyntax {
+    type Parsed = MatchArm<'s>;
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Kw(KwKind::Case))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        p.expect(Token::Kw(KwKind::Case))?;
+        let pat = p.expect(MatchPattern)?;
+        match p.expect(Token::Sigil)? {
+            td if td.span == "=>" => (),
+            td => {
+                let msg = format!("Expected `=>` after the pattern, found `{}`", td.span);
+                return Err(RecoveryInfo::InvalidToken(msg));
+            }
+        }
+
+        // newlines after the `=>` sigil are skipped like after an operator
+        let body = p.expect(BlockBody)?;
+        Ok(MatchArm { pat, body })
+    }
+}
+
+struct MatchExpr;
+impl<'s> Syntax<'s> for MatchExpr {
+    type Parsed = Expr<'s>;
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Kw(KwKind::Match))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
+        p.expect(Token::Kw(KwKind::Match))?;
+        let scrutinee = p.expect(LineOf(BinaryExpr))?;
+        let arms = p.expect(EndedOf(LinesOf(MatchArmSyntax)))?;
+        Ok(p.new_expr(start, ExprKind::Match { scrutinee, arms }))
+    }
+}
+
+// `step` is only special after a range
+struct RangeStep;
+impl<'s> Syntax<'s> for RangeStep {
+    type Parsed = Expr<'s>;
+    fn check(&self, p: &Parser<'s>) -> bool {
+        matches!(p.peek(), Some(td) if td.kind == Token::Ident && td.span == "step")
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        if !self.check(p) {
+            return Err(RecoveryInfo::InvalidToken(String::from("Expected `step`")));
+        }
+        p.shift();
+        p.expect(BinaryExpr)
+    }
+}
+
+struct ForStmt;
+impl<'s> Syntax<'s> for ForStmt {
+    type Parsed = Expr<'s>;
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Kw(KwKind::For))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
+        p.expect(Token::Kw(KwKind::For))?;
+        let var = p.expect(Token::Ident)?;
+        p.expect(Token::Kw(KwKind::In))?;
+
+        let range_start = p.expect(BinaryExpr)?;
+        p.expect(Token::Dot)?;
+        p.expect(Token::Dot)?;
+        let range_end = p.expect(BinaryExpr)?;
+        let step = p.parse_if_present(RangeStep).transpose()?;
+        p.expect(LineEnd)?;
+
+        let range = ForRange {
+            var,
+            var_nid: p.nid.shift(),
+            start: range_start,
+            end: range_end,
+            step,
+        };
+        let for_body = p.expect(StmtBlock)?;
+        Ok(p.new_expr(start, ExprKind::For { range, for_body }))
+    }
+}
+
+// `break` or `continue`, checked to be in a loop after parsing
+struct JumpStmt;
+impl<'s> Syntax<'s> for JumpStmt {
+    type Parsed = Expr<'s>;
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(AnyOf(&[
+            Token::Kw(KwKind::Break),
+            Token::Kw(KwKind::Continue),
+        ]))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
+        let kw = p.expect(AnyOf(&[
+            Token::Kw(KwKind::Break),
+            Token::Kw(KwKind::Continue),
+        ]))?;
+
+        let kind = match kw.kind {
+            Token::Kw(KwKind::Break) => {
+                ExprKind::Break(p.parse_if_present(Expression).transpose()?)
+            }
+            _ => ExprKind::Continue,
+        };
+        Ok(p.new_expr(start, kind))
+    }
+}
+
+struct ReturnStmt;
+impl<'s> Syntax<'s> for ReturnStmt {
+    type Parsed = Expr<'s>;
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Kw(KwKind::Return))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
+        p.expect(Token::Kw(KwKind::Return))?;
+        let value = p.expect(Expression)?;
+        Ok(p.new_expr(start, ExprKind::Return(value)))
     }
 }
 
@@ -479,8 +974,9 @@ impl<'s> Syntax<'s> for BlockBody {
     }
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
-        let body = p.expect(LinesOf(Statement))?;
-        Ok(p.new_expr(ExprKind::Block(body)))
+        let start = p.peek_loc();
+        let body = p.expect(LinesOf(Recover(Statement, InvalidStmt)))?;
+        Ok(p.new_expr(start, ExprKind::Block(body)))
     }
 }
 
@@ -493,7 +989,17 @@ impl<'s> Syntax<'s> for Statement {
             Some(tok) => match tok.kind {
                 Token::Kw(kw) => matches!(
                     kw,
-                    KwKind::If | KwKind::Let | KwKind::Val | KwKind::While | KwKind::Do
+                    KwKind::If
+                        | KwKind::Let
+                        | KwKind::Val
+                        | KwKind::While
+                        | KwKind::Loop
+                        | KwKind::Match
+                        | KwKind::For
+                        | KwKind::Do
+                        | KwKind::Break
+                        | KwKind::Continue
+                        | KwKind::Return
                 ),
 
                 _ => p.check(BinaryExpr),
@@ -511,7 +1017,12 @@ impl<'s> Syntax<'s> for Statement {
                     KwKind::Val => p.expect(ValMutDecl),
                     KwKind::Do => p.expect(DoExpr),
                     KwKind::While => p.expect(WhileStmt),
-                    _ => unimplemented!("Bad Keyword {:?}", kw),
+                    KwKind::Loop => p.expect(LoopExpr),
+                    KwKind::Match => p.expect(MatchExpr),
+                    KwKind::For => p.expect(ForStmt),
+                    KwKind::Break | KwKind::Continue => p.expect(JumpStmt),
+                    KwKind::Return => p.expect(ReturnStmt),
+                    _ => Err(bad_keyword(kw, "a statement")),
                 },
                 _ => p.expect(BinaryExpr),
             },
@@ -531,3 +1042,95 @@ impl<'s> Syntax<'s> for StmtBlock {
         p.expect(EndedOf(BlockBody))
     }
 }
+
+// Skips the rest of a broken statement
+// stops at the line end or at a keyword closing the enclosing block
+struct InvalidStmt;
+impl<'s> Syntax<'s> for InvalidStmt {
+    type Parsed = Expr<'s>;
+
+    fn check(&self, _p: &Parser<'s>) -> bool {
+        true
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let start = p.peek_loc();
+        while let Some(td) = p.peek() {
+            match td.kind {
+                Token::Newline
+                | Token::SemiColon
+                | Token::Kw(KwKind::End)
+                | Token::Kw(KwKind::Else)
+                | Token::Kw(KwKind::Case)
+                | Token::Kw(KwKind::In)
+                | Token::Kw(KwKind::Fun)
+                | Token::Kw(KwKind::Extern) => break,
+                _ => p.shift(),
+            }
+        }
+        Ok(p.new_expr(start, ExprKind::Error))
+    }
+}
+
+impl<'s> InvalidSyntax<'s> for InvalidStmt {}
+
+// the expression giving the value of a function's body
+#[cfg(test)]
+fn tail_expr<'a, 's>(m: &'a crate::ast::Module<'s>, item: usize) -> &'a Expr<'s> {
+    let body = match &m.body[item].kind {
+        crate::ast::ItemKind::Func(f) => &f.body,
+        _ => panic!("expected a function"),
+    };
+    match &*body.kind {
+        ExprKind::Block(block) => block.last().unwrap(),
+        _ => body,
+    }
+}
+
+#[test]
+fn test_user_op_binds_by_its_precedence() {
+    use crate::cli::check::expect_checked;
+
+    let src =
+        "mod m\nfun binary|> 5 (x, f)\n    x * 10 + f\nend\nfun main()\n    1 |> 2 + 3\nend\n";
+    let checked = expect_checked(src);
+
+    // `|>` binds looser than `+`
+    match &*tail_expr(&checked.module, 1).kind {
+        ExprKind::Call { args, .. } => assert!(matches!(&*args[1].kind, ExprKind::Binary { .. })),
+        _ => panic!("expected `|>` to parse as a call"),
+    }
+}
+
+#[test]
+fn test_index_binds_tighter_than_prefix_ops() {
+    use crate::cli::check::expect_checked;
+
+    let src = "mod m\nfun f(a: [[f64]]): f64\n    -a[0][1]\nend\n";
+    let checked = expect_checked(src);
+
+    let operand = match &*tail_expr(&checked.module, 0).kind {
+        ExprKind::Unary { operand, .. } => operand,
+        _ => panic!("expected `-` outside the index"),
+    };
+    match &*operand.kind {
+        ExprKind::Index { base, .. } => assert!(matches!(&*base.kind, ExprKind::Index { .. })),
+        _ => panic!("expected `a[0][1]` to index twice"),
+    }
+}
+
+#[test]
+fn test_nested_tuple_field_splits_float_token() {
+    use crate::cli::check::expect_checked;
+
+    let src = "mod m\nfun f(t: ((i64, i64), i64)): i64\n    t.0.1\nend\n";
+    let checked = expect_checked(src);
+
+    // `0.1` is lexed as one float, but read as two fields
+    match &*tail_expr(&checked.module, 0).kind {
+        ExprKind::TupleField { base, index: 1 } => {
+            assert!(matches!(&*base.kind, ExprKind::TupleField { index: 0, .. }))
+        }
+        _ => panic!("expected `t.0.1` to read two fields"),
+    }
+}
@@ -1,13 +1,16 @@
-use super::comb::{AnyOf, DelimitedListOf, Either, EitherOf, LineOf, LinesOf};
+use super::comb::{
+    AnyOf, DelimitedListOf, Either, EitherOf, LineEnd, LineOf, LinesOf, Recover, SkipEmptyLines,
+};
 use super::delim::ParenDelim;
-use super::Syntax;
+use super::{InvalidSyntax, Syntax};
 
-use crate::parse::{ParseResult, Parser};
+use crate::parse::{ParseResult, Parser, RecoveryInfo};
 
 use crate::ast;
-use crate::ast::token::{KwKind, ReservedKind, Token, TokenData};
+use crate::ast::token::{KwKind, LitKind, ReservedKind, Token, TokenData};
 
 use super::stmt::{Statement, StmtBlock};
+use super::types::{TypeAnnotation, TypeName};
 
 struct FuncArg;
 impl<'s> Syntax<'s> for FuncArg {
@@ -19,10 +22,58 @@ impl<'s> Syntax<'s> for FuncArg {
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
         let td = p.expect(Token::Ident)?;
-        Ok(ast::FuncArg::new(p.nid.shift(), td))
+        let ty = p.parse_if_present(TypeAnnotation).transpose()?;
+        Ok(ast::FuncArg::new(p.nid.shift(), td, ty))
     }
 }
 
+// `...` as the last argument of a C function
+struct VarArgs;
+impl<'s> Syntax<'s> for VarArgs {
+    type Parsed = ();
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Dot)
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        for _ in 0..3 {
+            p.expect(Token::Dot)?;
+        }
+        Ok(())
+    }
+}
+
+// same level as comparisons
+const DEFAULT_USER_OP_PREC: i32 = 30;
+
+// `5` in `fun binary|> 5 (a, b)`
+struct OpPrecedence;
+impl<'s> Syntax<'s> for OpPrecedence {
+    type Parsed = i32;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Literal(LitKind::Int))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let td = p.expect(Token::Literal(LitKind::Int))?;
+        match td.span.parse::<i32>() {
+            Ok(prec) if (1..=100).contains(&prec) => Ok(prec),
+            _ => {
+                let msg = format!("Operator precedence must be 1 to 100, found {}", td.span);
+                Err(RecoveryInfo::InvalidToken(msg))
+            }
+        }
+    }
+}
+
+// operator functions are named by their sigil
+enum OpDecl {
+    Binary(i32),
+    Unary,
+}
+
 struct FuncProto;
 impl<'s> Syntax<'s> for FuncProto {
     type Parsed = ast::FuncProto<'s>;
@@ -42,19 +93,74 @@ impl<'s> Syntax<'s> for FuncProto {
             Token::Reserved(ReservedKind::Fn),
         ]);
         let td = p.expect(starting_tokens)?;
-        let name = p.expect(Token::Ident)?;
+        let mut op_decl = None;
+        let name = match p.expect(Token::Ident)? {
+            kind if matches!(kind.span, "binary" | "unary") && p.check(Token::Sigil) => {
+                let op = p.expect(Token::Sigil)?;
+                if p.is_builtin_op(op.span) {
+                    let msg = format!("Operator `{}` is built in and cannot be redefined", op.span);
+                    return Err(RecoveryInfo::InvalidOp(msg));
+                }
+
+                op_decl = Some(match kind.span {
+                    "binary" => {
+                        let prec = p.parse_if_present(OpPrecedence).transpose()?;
+                        OpDecl::Binary(prec.unwrap_or(DEFAULT_USER_OP_PREC))
+                    }
+                    _ => OpDecl::Unary,
+                });
+                op
+            }
+            name => name,
+        };
 
-        let args = p.expect(DelimitedListOf(ParenDelim, FuncArg))?;
+        let parsed_args = p.expect(DelimitedListOf(ParenDelim, EitherOf(FuncArg, VarArgs)))?;
+        let ret = p.parse_if_present(TypeAnnotation).transpose()?;
+
+        let mut args = Vec::with_capacity(parsed_args.len());
+        let mut variadic = false;
+        for arg in parsed_args {
+            match arg {
+                Either::First(arg) if !variadic => args.push(arg),
+                Either::Second(_) if !variadic => variadic = true,
+                _ => {
+                    let msg = String::from("`...` must be the last argument");
+                    return Err(RecoveryInfo::InvalidToken(msg));
+                }
+            }
+        }
 
         match td.kind {
             Token::Kw(KwKind::Fun) => (),
             Token::Reserved(ReservedKind::Def) | Token::Reserved(ReservedKind::Fn) => {
-                unimplemented!("Use of bad keyword to start function")
+                let msg = format!("Functions are declared with `fun`, not `{}`", td.span);
+                return Err(RecoveryInfo::InvalidToken(msg));
             }
             _ => unreachable!("Parsed function without acceptable function-start keyword"),
         }
 
-        Ok(ast::FuncProto { name, args })
+        if let Some(op) = op_decl {
+            let arity = match op {
+                OpDecl::Binary(_) => 2,
+                OpDecl::Unary => 1,
+            };
+            if variadic || args.len() != arity {
+                let msg = format!("Operator `{}` must take {} arguments", name.span, arity);
+                return Err(RecoveryInfo::InvalidToken(msg));
+            }
+
+            match op {
+                OpDecl::Binary(prec) => p.add_user_op(name.span, prec),
+                OpDecl::Unary => p.add_user_unary_op(name.span),
+            }
+        }
+
+        Ok(ast::FuncProto {
+            name,
+            args,
+            ret,
+            variadic,
+        })
     }
 }
 
@@ -68,6 +174,10 @@ impl<'s> Syntax<'s> for Function {
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
         let proto = p.expect(LineOf(FuncProto))?;
+        if proto.variadic {
+            let msg = String::from("Only extern functions can take `...` arguments");
+            return Err(RecoveryInfo::InvalidToken(msg));
+        }
         let body = p.expect(StmtBlock)?;
         Ok(ast::Function { proto, body })
     }
@@ -87,24 +197,165 @@ impl<'s> Syntax<'s> for ExternDecl {
     }
 }
 
+// `x: f64` in a struct
+struct FieldDef;
+impl<'s> Syntax<'s> for FieldDef {
+    type Parsed = ast::FieldDef<'s>;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Ident)
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let name = p.expect(Token::Ident)?;
+        let ty = p.expect(TypeAnnotation)?;
+        Ok(ast::FieldDef { name, ty })
+    }
+}
+
+// fields are separated by commas or new lines
+struct StructItem;
+impl<'s> Syntax<'s> for StructItem {
+    type Parsed = ast::StructDef<'s>;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Kw(KwKind::Struct))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        p.expect(Token::Kw(KwKind::Struct))?;
+        let name = p.expect(Token::Ident)?;
+
+        let mut fields = Vec::new();
+        loop {
+            p.expect(SkipEmptyLines)?;
+            if p.check(Token::Kw(KwKind::End)) {
+                break;
+            }
+
+            fields.push(p.expect(FieldDef)?);
+            if !p.check(Token::Kw(KwKind::End)) {
+                p.expect(EitherOf(Token::Comma, LineEnd))?;
+            }
+        }
+        p.expect(Token::Kw(KwKind::End))?;
+
+        Ok(ast::StructDef { name, fields })
+    }
+}
+
+// `Circle(f64)`, a variant without a payload leaves out the parentheses
+struct VariantDef;
+impl<'s> Syntax<'s> for VariantDef {
+    type Parsed = ast::VariantDef<'s>;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Ident)
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        let name = p.expect(Token::Ident)?;
+        let payload = p
+            .parse_if_present(DelimitedListOf(ParenDelim, TypeName))
+            .transpose()?
+            .unwrap_or_default();
+        Ok(ast::VariantDef {
+            name,
+            nid: p.nid.shift(),
+            payload,
+        })
+    }
+}
+
+// variants are separated by `|` or new lines
+struct EnumItem;
+impl<'s> Syntax<'s> for EnumItem {
+    type Parsed = ast::EnumDef<'s>;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Kw(KwKind::Enum))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        p.expect(Token::Kw(KwKind::Enum))?;
+        let name = p.expect(Token::Ident)?;
+
+        let mut variants = Vec::new();
+        loop {
+            p.expect(SkipEmptyLines)?;
+            if p.check(Token::Kw(KwKind::End)) {
+                break;
+            }
+
+            variants.push(p.expect(VariantDef)?);
+            match p.peek() {
+                Some(td) if td.kind == Token::Sigil && td.span == "|" => p.shift(),
+                Some(td) if td.kind == Token::Kw(KwKind::End) => (),
+                _ => p.expect(LineEnd)?,
+            }
+        }
+        p.expect(Token::Kw(KwKind::End))?;
+
+        if variants.is_empty() {
+            let msg = format!("Enum `{}` needs at least one variant", name.span);
+            return Err(RecoveryInfo::InvalidToken(msg));
+        }
+        Ok(ast::EnumDef { name, variants })
+    }
+}
+
 struct TopLevelItem;
 impl<'s> Syntax<'s> for TopLevelItem {
     type Parsed = ast::Item<'s>;
 
     fn check(&self, p: &Parser<'s>) -> bool {
-        p.check(EitherOf(ExternDecl, Function))
+        p.check(EitherOf(
+            EitherOf(StructItem, EnumItem),
+            EitherOf(ExternDecl, Function),
+        ))
     }
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
-        let new_item = match p.expect(EitherOf(ExternDecl, Function))? {
-            Either::First(ext) => ast::ItemKind::Extern(ext),
-            Either::Second(func) => ast::ItemKind::Func(func),
+        let item_grammer = EitherOf(
+            EitherOf(StructItem, EnumItem),
+            EitherOf(ExternDecl, Function),
+        );
+        let new_item = match p.expect(item_grammer)? {
+            Either::First(Either::First(def)) => ast::ItemKind::Struct(def),
+            Either::First(Either::Second(def)) => ast::ItemKind::Enum(def),
+            Either::Second(Either::First(ext)) => ast::ItemKind::Extern(ext),
+            Either::Second(Either::Second(func)) => ast::ItemKind::Func(func),
         };
 
         Ok(ast::Item::new(p.nid.shift(), new_item))
     }
 }
 
+// Skips a broken item up to the start of the next one
+struct InvalidItem;
+impl<'s> Syntax<'s> for InvalidItem {
+    type Parsed = ast::Item<'s>;
+
+    fn check(&self, _p: &Parser<'s>) -> bool {
+        true
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        while let Some(td) = p.peek() {
+            match td.kind {
+                Token::Kw(KwKind::Fun)
+                | Token::Kw(KwKind::Extern)
+                | Token::Kw(KwKind::Struct)
+                | Token::Kw(KwKind::Enum) => break,
+                _ => p.shift(),
+            }
+        }
+        Ok(ast::Item::new(p.nid.shift(), ast::ItemKind::Error))
+    }
+}
+
+impl<'s> InvalidSyntax<'s> for InvalidItem {}
+
 struct ModDecl;
 impl<'s> Syntax<'s> for ModDecl {
     type Parsed = TokenData<'s>;
@@ -129,7 +380,28 @@ impl<'s> Syntax<'s> for ModuleGrammer {
 
     fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
         let mod_decl = p.expect(LineOf(ModDecl))?;
-        let body = p.expect(LinesOf(TopLevelItem))?;
+
+        let mut body = Vec::new();
+        loop {
+            let items = p.expect(LinesOf(Recover(TopLevelItem, InvalidItem)))?;
+            body.extend(items);
+
+            // anything left over cannot start an item
+            match p.peek() {
+                Some(td) => {
+                    let msg = format!(
+                        "Expected a top-level item but found {:?} `{}`",
+                        td.kind,
+                        td.span.escape_default()
+                    );
+                    p.report_recovery(&RecoveryInfo::InvalidToken(msg));
+                    p.shift();
+                    body.push(p.expect(InvalidItem)?);
+                }
+                None => break,
+            }
+        }
+
         Ok(ast::Module {
             decl: mod_decl,
             body,
@@ -142,6 +414,8 @@ fn build_anon_func(body: ast::Expr) -> ast::Function {
     let proto = ast::FuncProto {
         name: name_data,
         args: Vec::new(),
+        ret: None,
+        variadic: false,
     };
 
     ast::Function { proto, body }
@@ -186,3 +460,39 @@ impl<'s> Syntax<'s> for ReplGrammer {
         Ok(body)
     }
 }
+
+#[test]
+fn test_recovers_to_report_every_syntax_error() {
+    use crate::cli::check::expect_errors;
+
+    let src = "mod m
+fun f(
+    1
+end
+fun g()
+    val = 2
+    val y = )
+    y
+end
+fun h()
+    3
+end
+";
+    let diags = expect_errors(src);
+
+    // a broken item, then two broken statements in one function
+    let locs: Vec<_> = diags
+        .iter()
+        .map(|d| {
+            (
+                d.code,
+                d.labels[0].span.start.line,
+                d.labels[0].span.start.column,
+            )
+        })
+        .collect();
+    assert_eq!(
+        locs,
+        vec![("E0100", 3, 5), ("E0100", 6, 9), ("E0100", 7, 13)]
+    );
+}
@@ -0,0 +1,73 @@
+use super::comb::DelimitedListOf;
+use super::delim::ParenDelim;
+use super::Syntax;
+
+use crate::parse::{ParseResult, Parser, RecoveryInfo};
+
+use crate::ast::token::{KwKind, Token};
+use crate::ast::Type;
+
+// built-in type names such as i64 or bool, `[type]` for arrays,
+// `(type, type)` for tuples or `fun(type): type` for functions
+// any other name is a struct or enum, name resolution checks it exists
+pub struct TypeName;
+impl<'s> Syntax<'s> for TypeName {
+    type Parsed = Type;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::Ident)
+            || p.check(Token::OpenSquare)
+            || p.check(Token::OpenParen)
+            || p.check(Token::Kw(KwKind::Fun))
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        if p.check(Token::OpenSquare) {
+            p.expect(Token::OpenSquare)?;
+            let elem = p.expect(TypeName)?;
+            p.expect(Token::ClosedSquare)?;
+            return Ok(Type::array(elem));
+        }
+
+        // without a return type the function returns nil
+        if p.check(Token::Kw(KwKind::Fun)) {
+            p.expect(Token::Kw(KwKind::Fun))?;
+            let args = p.expect(DelimitedListOf(ParenDelim, TypeName))?;
+            let ret = p.parse_if_present(TypeAnnotation).transpose()?;
+            return Ok(Type::func(args, ret.unwrap_or_else(Type::nil)));
+        }
+
+        // parentheses around a single type only group it
+        if p.check(Token::OpenParen) {
+            let mut elems = p.expect(DelimitedListOf(ParenDelim, TypeName))?;
+            return match elems.len() {
+                0 => Err(RecoveryInfo::InvalidToken(String::from(
+                    "Empty tuple types are not supported, use `nil`",
+                ))),
+                1 => Ok(elems.pop().unwrap()),
+                _ => Ok(Type::tuple(elems)),
+            };
+        }
+
+        let td = p.expect(Token::Ident)?;
+        match Type::from_name(td.span) {
+            Some(ty) => Ok(ty),
+            None => Ok(Type::named(td.span)),
+        }
+    }
+}
+
+// `: type` after a name
+pub struct TypeAnnotation;
+impl<'s> Syntax<'s> for TypeAnnotation {
+    type Parsed = Type;
+
+    fn check(&self, p: &Parser<'s>) -> bool {
+        p.check(Token::ColonSingle)
+    }
+
+    fn expect(&self, p: &mut Parser<'s>) -> ParseResult<Self::Parsed> {
+        p.expect(Token::ColonSingle)?;
+        p.expect(TypeName)
+    }
+}